@@ -0,0 +1,575 @@
+//! Input bindings module
+//!
+//! Maps keys, mouse buttons and the scroll wheel (with Ctrl/Shift/Alt
+//! modifiers) to app actions. Bindings are written as text such as
+//! `"Ctrl+Shift+Z"` or `"Shift+Scroll"` in the `[bindings]` config section;
+//! an action may list several, separated by commas.
+
+use crate::config::BindingsConfig;
+use std::fmt;
+use winit::event::MouseButton;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+/// Number of saved views that have their own "go to" binding
+pub const VIEW_BOOKMARK_SLOTS: usize = 9;
+
+/// Something the user can trigger with a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Pick up and move the object under the cursor
+    Drag,
+    /// Customize the object under the cursor, or toggle the palette over
+    /// empty space
+    ContextClick,
+    /// Rotate the held object
+    Rotate,
+    /// Scale the held object
+    Scale,
+    /// Delete the held object
+    DeleteObject,
+    /// Add an object of the selected type
+    AddObject,
+    /// Select the next object type for `AddObject`
+    CycleType,
+    /// Open or close the palette
+    TogglePalette,
+    /// Close all panels
+    ClosePanels,
+    /// Undo the last desk edit
+    Undo,
+    /// Redo the last undone desk edit
+    Redo,
+    /// Orbit or pan the camera while held
+    CameraDrag,
+    /// Switch to the next camera mode
+    CycleCameraMode,
+    /// Turn the orbit, top-down or isometric view a quarter turn
+    RotateView,
+    /// Move the camera back to the default view
+    ResetView,
+    /// Move the camera to the next saved view bookmark
+    NextViewBookmark,
+    /// Move the camera to the saved view bookmark at this index (0 is the
+    /// first), below `VIEW_BOOKMARK_SLOTS`
+    ViewBookmark(usize),
+}
+
+/// Descriptions of the `ViewBookmark` actions, by slot
+const VIEW_BOOKMARK_DESCRIPTIONS: [&str; VIEW_BOOKMARK_SLOTS] = [
+    "Go to saved view 1",
+    "Go to saved view 2",
+    "Go to saved view 3",
+    "Go to saved view 4",
+    "Go to saved view 5",
+    "Go to saved view 6",
+    "Go to saved view 7",
+    "Go to saved view 8",
+    "Go to saved view 9",
+];
+
+/// Config keys of the `ViewBookmark` actions, by slot
+const VIEW_BOOKMARK_KEYS: [&str; VIEW_BOOKMARK_SLOTS] = [
+    "view_bookmark_1",
+    "view_bookmark_2",
+    "view_bookmark_3",
+    "view_bookmark_4",
+    "view_bookmark_5",
+    "view_bookmark_6",
+    "view_bookmark_7",
+    "view_bookmark_8",
+    "view_bookmark_9",
+];
+
+/// Which kind of input an action can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    /// A mouse button
+    Mouse,
+    /// The scroll wheel
+    Scroll,
+    /// A key or mouse button
+    Press,
+}
+
+impl Action {
+    /// All actions, in priority order when bindings conflict
+    pub const ALL: &'static [Action] = &[
+        Action::Drag,
+        Action::ContextClick,
+        Action::Rotate,
+        Action::Scale,
+        Action::DeleteObject,
+        Action::AddObject,
+        Action::CycleType,
+        Action::TogglePalette,
+        Action::ClosePanels,
+        Action::Undo,
+        Action::Redo,
+        Action::CameraDrag,
+        Action::CycleCameraMode,
+        Action::RotateView,
+        Action::ResetView,
+        Action::NextViewBookmark,
+        Action::ViewBookmark(0),
+        Action::ViewBookmark(1),
+        Action::ViewBookmark(2),
+        Action::ViewBookmark(3),
+        Action::ViewBookmark(4),
+        Action::ViewBookmark(5),
+        Action::ViewBookmark(6),
+        Action::ViewBookmark(7),
+        Action::ViewBookmark(8),
+    ];
+
+    /// Description shown in the controls list and bindings window
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Drag => "Move object",
+            Action::ContextClick => "Customize object / toggle palette",
+            Action::Rotate => "Rotate held object",
+            Action::Scale => "Scale held object",
+            Action::DeleteObject => "Delete held object",
+            Action::AddObject => "Add selected object type",
+            Action::CycleType => "Cycle object type",
+            Action::TogglePalette => "Toggle palette",
+            Action::ClosePanels => "Close panels",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::CameraDrag => "Orbit / pan camera",
+            Action::CycleCameraMode => "Cycle camera mode",
+            Action::RotateView => "Turn view a quarter turn",
+            Action::ResetView => "Reset view",
+            Action::NextViewBookmark => "Next saved view",
+            Action::ViewBookmark(slot) => VIEW_BOOKMARK_DESCRIPTIONS[*slot],
+        }
+    }
+
+    /// Setting name in the `[bindings]` config section
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            Action::Drag => "drag",
+            Action::ContextClick => "context_click",
+            Action::Rotate => "rotate",
+            Action::Scale => "scale",
+            Action::DeleteObject => "delete_object",
+            Action::AddObject => "add_object",
+            Action::CycleType => "cycle_type",
+            Action::TogglePalette => "toggle_palette",
+            Action::ClosePanels => "close_panels",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::CameraDrag => "camera_drag",
+            Action::CycleCameraMode => "cycle_camera_mode",
+            Action::RotateView => "rotate_view",
+            Action::ResetView => "reset_view",
+            Action::NextViewBookmark => "next_view_bookmark",
+            Action::ViewBookmark(slot) => VIEW_BOOKMARK_KEYS[*slot],
+        }
+    }
+
+    /// Kind of input this action accepts
+    fn input_kind(&self) -> InputKind {
+        match self {
+            Action::Drag | Action::ContextClick | Action::CameraDrag => InputKind::Mouse,
+            Action::Rotate | Action::Scale => InputKind::Scroll,
+            _ => InputKind::Press,
+        }
+    }
+}
+
+/// A physical input that can be bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Scroll,
+}
+
+/// An input together with the modifiers that must be held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub input: Input,
+}
+
+/// Key names accepted in bindings
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::KeyA),
+    ("B", KeyCode::KeyB),
+    ("C", KeyCode::KeyC),
+    ("D", KeyCode::KeyD),
+    ("E", KeyCode::KeyE),
+    ("F", KeyCode::KeyF),
+    ("G", KeyCode::KeyG),
+    ("H", KeyCode::KeyH),
+    ("I", KeyCode::KeyI),
+    ("J", KeyCode::KeyJ),
+    ("K", KeyCode::KeyK),
+    ("L", KeyCode::KeyL),
+    ("M", KeyCode::KeyM),
+    ("N", KeyCode::KeyN),
+    ("O", KeyCode::KeyO),
+    ("P", KeyCode::KeyP),
+    ("Q", KeyCode::KeyQ),
+    ("R", KeyCode::KeyR),
+    ("S", KeyCode::KeyS),
+    ("T", KeyCode::KeyT),
+    ("U", KeyCode::KeyU),
+    ("V", KeyCode::KeyV),
+    ("W", KeyCode::KeyW),
+    ("X", KeyCode::KeyX),
+    ("Y", KeyCode::KeyY),
+    ("Z", KeyCode::KeyZ),
+    ("0", KeyCode::Digit0),
+    ("1", KeyCode::Digit1),
+    ("2", KeyCode::Digit2),
+    ("3", KeyCode::Digit3),
+    ("4", KeyCode::Digit4),
+    ("5", KeyCode::Digit5),
+    ("6", KeyCode::Digit6),
+    ("7", KeyCode::Digit7),
+    ("8", KeyCode::Digit8),
+    ("9", KeyCode::Digit9),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+    ("Escape", KeyCode::Escape),
+    ("Delete", KeyCode::Delete),
+    ("Backspace", KeyCode::Backspace),
+    ("Insert", KeyCode::Insert),
+    ("Tab", KeyCode::Tab),
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Enter),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Up", KeyCode::ArrowUp),
+    ("Down", KeyCode::ArrowDown),
+    ("Left", KeyCode::ArrowLeft),
+    ("Right", KeyCode::ArrowRight),
+    ("Minus", KeyCode::Minus),
+    ("Equal", KeyCode::Equal),
+    ("Comma", KeyCode::Comma),
+    ("Period", KeyCode::Period),
+    ("Slash", KeyCode::Slash),
+    ("Backquote", KeyCode::Backquote),
+];
+
+/// Mouse input names accepted in bindings
+const MOUSE_NAMES: &[(&str, MouseButton)] = &[
+    ("LeftMouse", MouseButton::Left),
+    ("RightMouse", MouseButton::Right),
+    ("MiddleMouse", MouseButton::Middle),
+    ("BackMouse", MouseButton::Back),
+    ("ForwardMouse", MouseButton::Forward),
+];
+
+impl Binding {
+    /// Parse a binding such as `"Ctrl+Shift+Z"` (case-insensitive)
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut binding = Binding {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            input: Input::Scroll,
+        };
+        let parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let Some((name, modifiers)) = parts.split_last() else {
+            return Err("empty binding".to_string());
+        };
+
+        for modifier in modifiers {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" => binding.alt = true,
+                _ => return Err(format!("unknown modifier \"{}\" in \"{}\"", modifier, text)),
+            }
+        }
+
+        let matches = |candidate: &&str| candidate.eq_ignore_ascii_case(name);
+        binding.input = if name.eq_ignore_ascii_case("Scroll") {
+            Input::Scroll
+        } else if let Some((_, key)) = KEY_NAMES.iter().find(|(n, _)| matches(n)) {
+            Input::Key(*key)
+        } else if let Some((_, button)) = MOUSE_NAMES.iter().find(|(n, _)| matches(n)) {
+            Input::Mouse(*button)
+        } else if name.is_empty() {
+            return Err(format!("missing key in \"{}\"", text));
+        } else {
+            return Err(format!("unknown key \"{}\"", name));
+        };
+        Ok(binding)
+    }
+
+    /// Whether the held modifiers are exactly the ones this binding needs
+    fn modifiers_match(&self, modifiers: ModifiersState) -> bool {
+        self.ctrl == modifiers.control_key()
+            && self.shift == modifiers.shift_key()
+            && self.alt == modifiers.alt_key()
+    }
+
+    fn fits(&self, kind: InputKind) -> bool {
+        matches!(
+            (kind, self.input),
+            (InputKind::Mouse, Input::Mouse(_))
+                | (InputKind::Scroll, Input::Scroll)
+                | (InputKind::Press, Input::Key(_) | Input::Mouse(_))
+        )
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        match self.input {
+            Input::Scroll => write!(f, "Scroll"),
+            Input::Key(key) => match KEY_NAMES.iter().find(|(_, k)| *k == key) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", key),
+            },
+            Input::Mouse(button) => match MOUSE_NAMES.iter().find(|(_, b)| *b == button) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", button),
+            },
+        }
+    }
+}
+
+/// Parse an action's comma-separated binding list
+pub fn parse_bindings(action: Action, text: &str) -> Result<Vec<Binding>, String> {
+    let bindings = text
+        .split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(Binding::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(binding) = bindings.iter().find(|b| !b.fits(action.input_kind())) {
+        let expected = match action.input_kind() {
+            InputKind::Mouse => "a mouse button",
+            InputKind::Scroll => "Scroll",
+            InputKind::Press => "a key or mouse button",
+        };
+        return Err(format!("{} cannot be bound to {}, expected {}", action.description(), binding, expected));
+    }
+    Ok(bindings)
+}
+
+/// Resolved bindings used to dispatch input
+#[derive(Debug, Clone, Default)]
+pub struct KeyMap {
+    bindings: Vec<(Action, Binding)>,
+    conflicts: Vec<String>,
+}
+
+impl KeyMap {
+    /// Resolve the configured bindings
+    ///
+    /// A binding used by more than one action stays with the action listed
+    /// first in `Action::ALL` and is reported as a conflict. Bindings that
+    /// don't parse are skipped and reported too.
+    pub fn from_config(config: &BindingsConfig) -> Self {
+        let mut map = KeyMap::default();
+        for &action in Action::ALL {
+            let bindings = match parse_bindings(action, config.get(action)) {
+                Ok(bindings) => bindings,
+                Err(e) => {
+                    map.conflicts.push(e);
+                    continue;
+                }
+            };
+            for binding in bindings {
+                match map.bindings.iter().find(|(_, b)| *b == binding) {
+                    Some((owner, _)) => map.conflicts.push(format!(
+                        "{} is bound to both \"{}\" and \"{}\"; keeping \"{}\"",
+                        binding,
+                        owner.description(),
+                        action.description(),
+                        owner.description()
+                    )),
+                    None => map.bindings.push((action, binding)),
+                }
+            }
+        }
+        map
+    }
+
+    /// Problems found while resolving the bindings
+    pub fn conflicts(&self) -> &[String] {
+        &self.conflicts
+    }
+
+    fn find(&self, input: Input, modifiers: ModifiersState) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, b)| b.input == input && b.modifiers_match(modifiers))
+            .map(|(action, _)| *action)
+    }
+
+    /// Action bound to a key press
+    pub fn key_action(&self, key: KeyCode, modifiers: ModifiersState) -> Option<Action> {
+        self.find(Input::Key(key), modifiers)
+    }
+
+    /// Action bound to a mouse button press
+    pub fn mouse_action(&self, button: MouseButton, modifiers: ModifiersState) -> Option<Action> {
+        self.find(Input::Mouse(button), modifiers)
+    }
+
+    /// Action bound to the scroll wheel
+    pub fn scroll_action(&self, modifiers: ModifiersState) -> Option<Action> {
+        self.find(Input::Scroll, modifiers)
+    }
+
+    /// Bindings of an action joined for display, e.g. `"Ctrl+Y / Ctrl+Shift+Z"`
+    pub fn label(&self, action: Action) -> String {
+        let labels: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(a, _)| *a == action)
+            .map(|(_, b)| b.to_string())
+            .collect();
+        if labels.is_empty() {
+            "unbound".to_string()
+        } else {
+            labels.join(" / ")
+        }
+    }
+
+    /// One `"<bindings> - <description>"` line per action, with the saved
+    /// view slots summed up in one line
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Action::ALL
+            .iter()
+            .filter(|action| !matches!(action, Action::ViewBookmark(_)))
+            .map(|action| format!("{} - {}", self.label(*action), action.description()))
+            .collect();
+        let slots: Vec<String> = (0..VIEW_BOOKMARK_SLOTS)
+            .map(|slot| self.label(Action::ViewBookmark(slot)))
+            .filter(|label| label != "unbound")
+            .collect();
+        if !slots.is_empty() {
+            lines.push(format!("{} - Go to saved view 1-{}", slots.join(" "), VIEW_BOOKMARK_SLOTS));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(ctrl: bool, shift: bool, alt: bool, code: KeyCode) -> Binding {
+        Binding { ctrl, shift, alt, input: Input::Key(code) }
+    }
+
+    #[test]
+    fn parse_ignores_case_and_spaces() {
+        assert_eq!(Binding::parse("ctrl+shift+z"), Ok(key(true, true, false, KeyCode::KeyZ)));
+        assert_eq!(Binding::parse(" Control + Y "), Ok(key(true, false, false, KeyCode::KeyY)));
+        assert_eq!(Binding::parse("alt+PAGEUP"), Ok(key(false, false, true, KeyCode::PageUp)));
+        assert_eq!(Binding::parse("home"), Ok(key(false, false, false, KeyCode::Home)));
+    }
+
+    #[test]
+    fn parse_mouse_and_scroll() {
+        let scroll = Binding::parse("Shift+scroll").unwrap();
+        assert_eq!(scroll.input, Input::Scroll);
+        assert!(scroll.shift);
+        assert_eq!(Binding::parse("middlemouse").unwrap().input, Input::Mouse(MouseButton::Middle));
+    }
+
+    #[test]
+    fn parse_rejects_bad_bindings() {
+        assert!(Binding::parse("Ctrl+").is_err());
+        assert!(Binding::parse("").is_err());
+        assert!(Binding::parse("Foo").is_err());
+        assert!(Binding::parse("Super+A").is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in ["Ctrl+Shift+Z", "Alt+F4", "Shift+Scroll", "RightMouse", "Delete"] {
+            assert_eq!(Binding::parse(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn parse_bindings_checks_input_kind() {
+        assert!(parse_bindings(Action::Rotate, "A").is_err());
+        assert!(parse_bindings(Action::Drag, "A").is_err());
+        assert!(parse_bindings(Action::Undo, "Scroll").is_err());
+        assert!(parse_bindings(Action::Undo, "Ctrl+Z, BackMouse").is_ok());
+        assert!(parse_bindings(Action::Scale, "Ctrl+Scroll").is_ok());
+        assert!(parse_bindings(Action::CameraDrag, "MiddleMouse").is_ok());
+    }
+
+    #[test]
+    fn parse_bindings_splits_and_skips_empty_entries() {
+        let bindings = parse_bindings(Action::Redo, "Ctrl+Shift+Z, , Ctrl+Y").unwrap();
+        assert_eq!(bindings.len(), 2);
+        assert!(parse_bindings(Action::Redo, "").unwrap().is_empty());
+    }
+
+    #[test]
+    fn default_bindings_have_no_conflicts() {
+        let map = KeyMap::from_config(&BindingsConfig::default());
+        assert!(map.conflicts().is_empty(), "{:?}", map.conflicts());
+        assert_eq!(map.key_action(KeyCode::KeyZ, ModifiersState::CONTROL), Some(Action::Undo));
+        assert_eq!(map.key_action(KeyCode::Digit3, ModifiersState::empty()), Some(Action::ViewBookmark(2)));
+        assert_eq!(map.scroll_action(ModifiersState::SHIFT), Some(Action::Scale));
+    }
+
+    #[test]
+    fn modifiers_must_match_exactly() {
+        let map = KeyMap::from_config(&BindingsConfig::default());
+        assert_eq!(map.key_action(KeyCode::KeyZ, ModifiersState::empty()), None);
+        assert_eq!(
+            map.key_action(KeyCode::KeyZ, ModifiersState::CONTROL | ModifiersState::SHIFT),
+            Some(Action::Redo)
+        );
+    }
+
+    #[test]
+    fn conflicting_binding_stays_with_first_action() {
+        let mut config = BindingsConfig::default();
+        *config.get_mut(Action::CycleType) = "A".to_string();
+        *config.get_mut(Action::AddObject) = "A, T".to_string();
+        let map = KeyMap::from_config(&config);
+
+        assert_eq!(map.key_action(KeyCode::KeyA, ModifiersState::empty()), Some(Action::AddObject));
+        assert_eq!(map.key_action(KeyCode::KeyT, ModifiersState::empty()), Some(Action::AddObject));
+        assert_eq!(map.label(Action::CycleType), "unbound");
+        assert_eq!(map.conflicts().len(), 1);
+    }
+
+    #[test]
+    fn invalid_binding_is_reported_and_skipped() {
+        let mut config = BindingsConfig::default();
+        *config.get_mut(Action::Undo) = "Ctrl+Nope".to_string();
+        let map = KeyMap::from_config(&config);
+
+        assert_eq!(map.label(Action::Undo), "unbound");
+        assert_eq!(map.conflicts().len(), 1);
+        assert_eq!(map.key_action(KeyCode::KeyY, ModifiersState::CONTROL), Some(Action::Redo));
+    }
+}
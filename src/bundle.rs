@@ -0,0 +1,199 @@
+//! Desk bundle module
+//!
+//! Exports a desk to a single self-describing file that teammates can import,
+//! either replacing their desk or merging into it with fresh object IDs.
+
+use crate::desk_object::{DeskObject, ObjectType};
+use crate::state::{migrate_value, write_atomic, AppState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Marker identifying a file as a desk bundle
+const BUNDLE_FORMAT: &str = "focus-desktop-simulator/desk-bundle";
+
+/// Current version of the bundle container format
+const BUNDLE_VERSION: u32 = 1;
+
+/// File extension used for bundles
+pub const BUNDLE_EXTENSION: &str = "deskbundle";
+
+/// Object fields that fall back to a default when missing
+const DEFAULTED_FIELDS: &[&str] = &[
+    "rotation",
+    "scale",
+    "color",
+    "accent_color",
+    "collision_radius_multiplier",
+    "collision_height_multiplier",
+];
+
+/// On-disk bundle container
+#[derive(Debug, Serialize, Deserialize)]
+struct DeskBundle {
+    /// Always `BUNDLE_FORMAT`
+    format: String,
+    /// Container format version
+    bundle_version: u32,
+    /// When the bundle was created (RFC 3339)
+    exported_at: String,
+    /// The exported desk state, in state file format
+    state: Value,
+}
+
+/// How imported objects are combined with the current desk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Add the imported objects next to the existing ones
+    Merge,
+    /// Remove all existing objects first
+    Replace,
+}
+
+/// Summary of an import
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Number of objects added to the desk
+    pub imported: usize,
+    /// Objects that were rejected, with the reason
+    pub skipped: Vec<String>,
+    /// Objects that were imported with some fields defaulted
+    pub defaulted: Vec<String>,
+    /// Invalid values that were fixed, as loading a state file would
+    pub repaired: Vec<String>,
+}
+
+impl ImportReport {
+    /// One-line summary for the UI and logs
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Imported {} objects", self.imported);
+        if !self.skipped.is_empty() {
+            summary.push_str(&format!(", skipped {}", self.skipped.len()));
+        }
+        if !self.defaulted.is_empty() {
+            summary.push_str(&format!(", {} with defaulted fields", self.defaulted.len()));
+        }
+        if !self.repaired.is_empty() {
+            summary.push_str(&format!(", {} values repaired", self.repaired.len()));
+        }
+        summary
+    }
+
+    /// Every skipped, defaulted and repaired object, one line each
+    pub fn details(&self) -> impl Iterator<Item = &String> {
+        self.skipped.iter().chain(&self.defaulted).chain(&self.repaired)
+    }
+}
+
+/// Write the given desk to a bundle file
+pub fn export_bundle(state: &AppState, path: &Path) -> Result<(), String> {
+    let bundle = DeskBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        bundle_version: BUNDLE_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        state: serde_json::to_value(state).map_err(|e| e.to_string())?,
+    };
+    let content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    write_atomic(path, content.as_bytes()).map_err(|e| format!("could not write bundle: {}", e))?;
+
+    log::info!("Exported {} objects to {:?}", state.objects.len(), path);
+    Ok(())
+}
+
+/// Read a bundle file and add its objects to the given desk
+///
+/// Every imported object gets a new ID from `state`, so bundles can be merged
+/// into a desk without clashing with existing objects. Objects with an unknown
+/// type or unusable position are skipped and listed in the report; invalid
+/// rotations and scales are repaired as on load. The caller still has to
+/// settle the objects onto its desk with `PhysicsEngine::settle_objects`.
+pub fn import_bundle(state: &mut AppState, path: &Path, mode: ImportMode) -> Result<ImportReport, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("could not read bundle: {}", e))?;
+    let bundle: DeskBundle =
+        serde_json::from_str(&content).map_err(|e| format!("not a desk bundle: {}", e))?;
+
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(format!("unknown bundle format \"{}\"", bundle.format));
+    }
+    if bundle.bundle_version > BUNDLE_VERSION {
+        return Err(format!(
+            "bundle version {} is newer than supported version {}",
+            bundle.bundle_version, BUNDLE_VERSION
+        ));
+    }
+
+    let mut desk = bundle.state;
+    migrate_value(&mut desk)?;
+    let raw_objects = match desk.get("objects") {
+        Some(Value::Array(objects)) => objects.clone(),
+        _ => return Err("bundle contains no object list".to_string()),
+    };
+
+    let mut report = ImportReport::default();
+    let mut objects = Vec::new();
+    for (index, raw) in raw_objects.iter().enumerate() {
+        match validate_object(raw) {
+            Ok((object, missing)) => {
+                if !missing.is_empty() {
+                    report.defaulted.push(format!(
+                        "object #{} ({}): defaulted {}",
+                        index,
+                        object.object_type.display_name(),
+                        missing.join(", ")
+                    ));
+                }
+                objects.push(object);
+            }
+            Err(reason) => report.skipped.push(format!("object #{}: {}", index, reason)),
+        }
+    }
+
+    if mode == ImportMode::Replace {
+        state.clear_objects();
+    }
+    for mut object in objects {
+        object.id = state.next_id();
+        object.target_y = object.position.y;
+        object.original_y = object.position.y;
+        state.add_object(object);
+        report.imported += 1;
+    }
+    report.repaired = state.repair();
+
+    for line in report.details() {
+        log::warn!("Import: {}", line);
+    }
+    log::info!("{} from {:?}", report.summary(), path);
+    Ok(report)
+}
+
+/// Check a raw object before accepting it, returning the parsed object and
+/// the names of fields that had to be defaulted
+fn validate_object(raw: &Value) -> Result<(DeskObject, Vec<&'static str>), String> {
+    let fields = raw.as_object().ok_or("not a JSON object")?;
+
+    let object_type = fields.get("object_type").ok_or("missing object_type")?;
+    serde_json::from_value::<ObjectType>(object_type.clone())
+        .map_err(|_| format!("unknown object type {}", object_type))?;
+
+    if !fields.contains_key("position") {
+        return Err("missing position".to_string());
+    }
+
+    let object: DeskObject =
+        serde_json::from_value(raw.clone()).map_err(|e| format!("invalid object: {}", e))?;
+    if !object.position.is_finite() {
+        return Err("position is not a finite number".to_string());
+    }
+    if !object.scale.is_finite() || object.scale <= 0.0 {
+        return Err(format!("invalid scale {}", object.scale));
+    }
+
+    let missing = DEFAULTED_FIELDS
+        .iter()
+        .copied()
+        .filter(|field| !fields.contains_key(*field))
+        .collect();
+    Ok((object, missing))
+}
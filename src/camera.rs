@@ -1,11 +1,207 @@
 //! Camera module for 3D rendering
 //!
-//! Implements a first-person style camera with yaw/pitch controls.
+//! Implements a first-person style camera with yaw/pitch controls, plus an
+//! orbit view around the desk and orthographic top-down and isometric views.
+//! Jumps between views (focusing an object, resetting, bookmarks) are eased
+//! transitions rather than cuts.
 
-use glam::{Mat4, Vec3};
-use crate::config::CONFIG;
+use glam::{Mat4, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI, TAU};
+use crate::config::CameraConfig;
+use crate::desk::Desk;
+use crate::desk_object::vec3_serde;
+
+/// Narrowest field of view accepted from the config or saved state, in
+/// degrees
+pub const MIN_FOV_DEGREES: f32 = 20.0;
+
+/// Widest field of view accepted from the config or saved state, in degrees
+pub const MAX_FOV_DEGREES: f32 = 120.0;
+
+/// Saved camera positions farther than this from the origin are ignored
+const MAX_RESTORED_DISTANCE: f32 = 50.0;
+
+/// Distance of the orthographic views' eye from their target
+///
+/// Only the lighting and fog see this distance; the projection itself has
+/// no perspective.
+const ORTHO_EYE_DISTANCE: f32 = 8.0;
+
+/// Depth range kept in front of and behind the orthographic eye
+const ORTHO_DEPTH: f32 = 100.0;
+
+/// Downward angle of a true isometric view, atan(1 / sqrt(2)) ≈ 35.26°
+const ISOMETRIC_PITCH: f32 = -0.615_479_7;
+
+/// Orbit pitch limits, keeping the orbit camera above the desk
+const MIN_ORBIT_PITCH: f32 = -1.50;
+const MAX_ORBIT_PITCH: f32 = -0.10;
+
+/// Orbit distance limits
+const MIN_ORBIT_DISTANCE: f32 = 1.0;
+const MAX_ORBIT_DISTANCE: f32 = 30.0;
+
+/// Limits for half the visible height of the orthographic views
+const MIN_ORTHO_HALF_HEIGHT: f32 = 0.5;
+const MAX_ORTHO_HALF_HEIGHT: f32 = 25.0;
+
+/// Orbit rotation per pixel of camera drag, in radians
+const ORBIT_DRAG_SPEED: f32 = 0.005;
+
+/// Zoom factor per scroll step
+const ZOOM_STEP: f32 = 0.9;
+
+/// Mouse-look rotation per pixel at sensitivity 1, in radians
+const LOOK_SPEED: f32 = 0.002;
+
+/// Length of an animated camera transition in seconds
+const TRANSITION_SECONDS: f32 = 0.6;
+
+/// How much larger than the object the focus view frames
+const FOCUS_MARGIN: f32 = 2.5;
+
+/// How the camera looks at the desk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CameraMode {
+    /// First-person view from the chair
+    #[default]
+    Perspective,
+    /// Perspective view circling the desk center
+    Orbit,
+    /// Orthographic view straight down, for planning the layout
+    TopDown,
+    /// Orthographic view from a true isometric angle
+    Isometric,
+}
+
+impl CameraMode {
+    /// All modes in switching order
+    pub const ALL: &'static [CameraMode] = &[
+        CameraMode::Perspective,
+        CameraMode::Orbit,
+        CameraMode::TopDown,
+        CameraMode::Isometric,
+    ];
+
+    /// Name shown in the UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CameraMode::Perspective => "Perspective",
+            CameraMode::Orbit => "Orbit",
+            CameraMode::TopDown => "Top-down",
+            CameraMode::Isometric => "Isometric",
+        }
+    }
+
+    /// The mode after this one in `ALL`, wrapping around
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|m| m == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Whether the mode uses an orthographic projection
+    pub fn is_orthographic(&self) -> bool {
+        matches!(self, CameraMode::TopDown | CameraMode::Isometric)
+    }
+}
+
+/// Everything needed to reproduce a camera view in any mode
+///
+/// `position`, `yaw` and `pitch` are the perspective pose, kept even while
+/// another mode is active, so a view restores both.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraView {
+    pub mode: CameraMode,
+    #[serde(with = "vec3_serde")]
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Field of view in degrees
+    pub fov: f32,
+    #[serde(with = "vec3_serde")]
+    pub target: Vec3,
+    pub orbit_distance: f32,
+    pub orbit_yaw: f32,
+    pub orbit_pitch: f32,
+    pub ortho_half_height: f32,
+}
+
+impl CameraView {
+    /// Whether every value is a usable number
+    fn is_finite(&self) -> bool {
+        self.position.is_finite()
+            && self.target.is_finite()
+            && [
+                self.yaw,
+                self.pitch,
+                self.fov,
+                self.orbit_distance,
+                self.orbit_yaw,
+                self.orbit_pitch,
+                self.ortho_half_height,
+            ]
+            .iter()
+            .all(|v| v.is_finite())
+    }
+
+    /// Blend towards another view; angles take the shorter way around
+    fn lerp(&self, to: &CameraView, t: f32) -> CameraView {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let lerp_angle = |a: f32, b: f32| a + wrap_angle(b - a) * t;
+        CameraView {
+            mode: to.mode,
+            position: self.position.lerp(to.position, t),
+            yaw: lerp_angle(self.yaw, to.yaw),
+            pitch: lerp(self.pitch, to.pitch),
+            fov: lerp(self.fov, to.fov),
+            target: self.target.lerp(to.target, t),
+            orbit_distance: lerp(self.orbit_distance, to.orbit_distance),
+            orbit_yaw: lerp_angle(self.orbit_yaw, to.orbit_yaw),
+            orbit_pitch: lerp(self.orbit_pitch, to.orbit_pitch),
+            ortho_half_height: lerp(self.ortho_half_height, to.ortho_half_height),
+        }
+    }
+}
+
+/// An eased move from one view to another
+#[derive(Debug, Clone)]
+struct Transition {
+    from: CameraView,
+    to: CameraView,
+    /// Seconds since the transition started
+    elapsed: f32,
+}
+
+/// Wrap an angle difference into [-PI, PI]
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// Ease-in-out curve mapping linear progress to eased progress
+fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+/// Where the orbit and orthographic views start, derived from the desk
+#[derive(Debug, Clone, Copy)]
+struct DeskFrame {
+    target: Vec3,
+    orbit_distance: f32,
+    ortho_half_height: f32,
+}
 
 /// Camera state and controls
+///
+/// `position`, `yaw` and `pitch` always describe the eye of the active mode.
+/// The other modes derive them from their target and angles, keeping the
+/// perspective pose aside so switching back returns to it.
 pub struct Camera {
     /// Current position
     pub position: Vec3,
@@ -21,8 +217,12 @@ pub struct Camera {
     pub near: f32,
     /// Far clipping plane
     pub far: f32,
-    /// Mouse sensitivity
+    /// Mouse-look rotation per pixel in radians
     pub sensitivity: f32,
+    /// Whether mouse-look pitch is inverted
+    pub invert_y: bool,
+    /// Configured field of view in radians, the widest the zoom goes
+    default_fov: f32,
     /// Minimum pitch angle (looking down limit)
     pub min_pitch: f32,
     /// Maximum pitch angle (looking up limit)
@@ -33,13 +233,29 @@ pub struct Camera {
     pub max_yaw: f32,
     /// Default yaw (for calculating limits)
     default_yaw: f32,
+    /// Active camera mode
+    pub mode: CameraMode,
+    /// Point the orbit, top-down and isometric views center on
+    pub target: Vec3,
+    /// Distance of the orbit camera from the target
+    pub orbit_distance: f32,
+    /// Heading of the orbit, top-down and isometric views in radians
+    pub orbit_yaw: f32,
+    /// Downward angle of the orbit camera in radians
+    pub orbit_pitch: f32,
+    /// Half the visible height of the orthographic views in world units
+    pub ortho_half_height: f32,
+    /// Perspective position, yaw and pitch while another mode is active
+    perspective_pose: (Vec3, f32, f32),
+    /// Starting view of the other modes for the current desk
+    desk_frame: DeskFrame,
+    /// Animated move in progress
+    transition: Option<Transition>,
 }
 
 impl Camera {
-    /// Create a new camera with default settings
-    pub fn new(aspect: f32) -> Self {
-        let config = &CONFIG.camera;
-
+    /// Create a new camera from the camera settings
+    pub fn new(aspect: f32, config: &CameraConfig) -> Self {
         // Calculate initial yaw and pitch from look_at target
         let (yaw, pitch) = Self::calculate_angles_from_look_at(
             config.position,
@@ -54,15 +270,281 @@ impl Camera {
             aspect,
             near: config.near,
             far: config.far,
-            sensitivity: 0.002,
+            sensitivity: LOOK_SPEED * config.mouse_sensitivity,
+            invert_y: config.invert_y,
+            default_fov: config.fov.to_radians(),
             min_pitch: -1.55,  // ~89 degrees down
             max_pitch: 0.42,   // ~24 degrees up
             min_yaw: yaw - 1.40,  // ~80 degrees left
             max_yaw: yaw + 1.40,  // ~80 degrees right
             default_yaw: yaw,
+            mode: CameraMode::Perspective,
+            target: config.look_at,
+            orbit_distance: (config.position - config.look_at).length(),
+            orbit_yaw: yaw,
+            orbit_pitch: pitch.clamp(MIN_ORBIT_PITCH, MAX_ORBIT_PITCH),
+            ortho_half_height: 5.0,
+            perspective_pose: (config.position, yaw, pitch),
+            desk_frame: DeskFrame {
+                target: config.look_at,
+                orbit_distance: (config.position - config.look_at).length(),
+                ortho_half_height: 5.0,
+            },
+            transition: None,
+        }
+    }
+
+    /// Center the orbit and orthographic views on the desk, sized to show
+    /// all of it
+    pub fn frame_desk(&mut self, desk: &Desk) {
+        let radius = desk.half_width.hypot(desk.half_depth);
+        self.transition = None;
+        self.desk_frame = DeskFrame {
+            target: Vec3::new(0.0, desk.surface_y, 0.0),
+            orbit_distance: (radius * 1.4).clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE),
+            ortho_half_height: (radius * 0.75).clamp(MIN_ORTHO_HALF_HEIGHT, MAX_ORTHO_HALF_HEIGHT),
+        };
+        self.target = self.desk_frame.target;
+        self.orbit_distance = self.desk_frame.orbit_distance;
+        self.ortho_half_height = self.desk_frame.ortho_half_height;
+        self.update_pose();
+    }
+
+    /// Switch to another camera mode
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == self.mode {
+            return;
+        }
+        self.transition = None;
+        if self.mode == CameraMode::Perspective {
+            self.perspective_pose = (self.position, self.yaw, self.pitch);
+        }
+        self.mode = mode;
+        if mode == CameraMode::Perspective {
+            (self.position, self.yaw, self.pitch) = self.perspective_pose;
+        } else {
+            self.update_pose();
         }
     }
 
+    /// Position, yaw and pitch of the perspective view, even while another
+    /// mode is active
+    pub fn perspective_pose(&self) -> (Vec3, f32, f32) {
+        if self.mode == CameraMode::Perspective {
+            (self.position, self.yaw, self.pitch)
+        } else {
+            self.perspective_pose
+        }
+    }
+
+    /// Recompute the eye of the orbit and orthographic views from their
+    /// target and angles
+    fn update_pose(&mut self) {
+        let (yaw, pitch, distance) = match self.mode {
+            CameraMode::Perspective => return,
+            CameraMode::Orbit => (self.orbit_yaw, self.orbit_pitch, self.orbit_distance),
+            CameraMode::TopDown => (self.orbit_yaw, -FRAC_PI_2, ORTHO_EYE_DISTANCE),
+            CameraMode::Isometric => (self.orbit_yaw + FRAC_PI_4, ISOMETRIC_PITCH, ORTHO_EYE_DISTANCE),
+        };
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.position = self.target - self.look_direction() * distance;
+    }
+
+    /// Move the view by a camera drag of the given pixels
+    ///
+    /// The perspective view looks around within its limits, orbit circles
+    /// the target, and the orthographic views pan across the desk so the
+    /// point under the cursor follows it.
+    pub fn drag_view(&mut self, delta_x: f32, delta_y: f32, viewport_height: f32) {
+        self.transition = None;
+        match self.mode {
+            CameraMode::Perspective => {
+                self.rotate(delta_x, delta_y);
+                return;
+            }
+            CameraMode::Orbit => {
+                self.orbit_yaw -= delta_x * ORBIT_DRAG_SPEED;
+                self.orbit_pitch =
+                    (self.orbit_pitch - delta_y * ORBIT_DRAG_SPEED).clamp(MIN_ORBIT_PITCH, MAX_ORBIT_PITCH);
+            }
+            CameraMode::TopDown | CameraMode::Isometric => {
+                let units_per_pixel = 2.0 * self.ortho_half_height / viewport_height.max(1.0);
+                // Screen-up distances shrink on the ground by the view's slope
+                let ground_scale = 1.0 / (-self.pitch).sin().max(0.1);
+                self.target -= self.right() * delta_x * units_per_pixel;
+                self.target += self.forward() * delta_y * units_per_pixel * ground_scale;
+            }
+        }
+        self.update_pose();
+    }
+
+    /// Zoom in (positive steps) or out
+    ///
+    /// The perspective view narrows its field of view, never widening past
+    /// the configured one; orbit moves closer and the orthographic views
+    /// show less of the desk.
+    pub fn zoom(&mut self, steps: f32) {
+        self.transition = None;
+        let factor = ZOOM_STEP.powf(steps);
+        match self.mode {
+            CameraMode::Perspective => {
+                let min_fov = MIN_FOV_DEGREES.to_radians().min(self.default_fov);
+                self.fov = (self.fov * factor).clamp(min_fov, self.default_fov);
+                return;
+            }
+            CameraMode::Orbit => {
+                self.orbit_distance =
+                    (self.orbit_distance * factor).clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
+            }
+            CameraMode::TopDown | CameraMode::Isometric => {
+                self.ortho_half_height =
+                    (self.ortho_half_height * factor).clamp(MIN_ORTHO_HALF_HEIGHT, MAX_ORTHO_HALF_HEIGHT);
+            }
+        }
+        self.update_pose();
+    }
+
+    /// Turn the orbit and orthographic views a quarter turn around the target
+    pub fn rotate_view(&mut self) {
+        if self.mode == CameraMode::Perspective {
+            return;
+        }
+        self.transition = None;
+        self.orbit_yaw += FRAC_PI_2;
+        self.update_pose();
+    }
+
+    /// Snapshot of the current view
+    pub fn view(&self) -> CameraView {
+        let (position, yaw, pitch) = self.perspective_pose();
+        CameraView {
+            mode: self.mode,
+            position,
+            yaw,
+            pitch,
+            fov: self.fov.to_degrees(),
+            target: self.target,
+            orbit_distance: self.orbit_distance,
+            orbit_yaw: self.orbit_yaw,
+            orbit_pitch: self.orbit_pitch,
+            ortho_half_height: self.ortho_half_height,
+        }
+    }
+
+    /// Apply a view immediately; the view's mode must be the active one
+    fn set_view(&mut self, view: &CameraView) {
+        self.fov = view.fov.to_radians();
+        self.target = view.target;
+        self.orbit_distance = view.orbit_distance;
+        self.orbit_yaw = view.orbit_yaw;
+        self.orbit_pitch = view.orbit_pitch;
+        self.ortho_half_height = view.ortho_half_height;
+        if self.mode == CameraMode::Perspective {
+            (self.position, self.yaw, self.pitch) = (view.position, view.yaw, view.pitch);
+        } else {
+            self.perspective_pose = (view.position, view.yaw, view.pitch);
+            self.update_pose();
+        }
+    }
+
+    /// Bring a view within the camera limits
+    fn clamp_view(&self, mut view: CameraView) -> CameraView {
+        // Yaw limits are relative to the default heading, so compare angles
+        // on that side of the wrap-around
+        view.yaw = (self.default_yaw + wrap_angle(view.yaw - self.default_yaw)).clamp(self.min_yaw, self.max_yaw);
+        view.pitch = view.pitch.clamp(self.min_pitch, self.max_pitch);
+        view.fov = view.fov.clamp(MIN_FOV_DEGREES, MAX_FOV_DEGREES);
+        view.orbit_distance = view.orbit_distance.clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
+        view.orbit_pitch = view.orbit_pitch.clamp(MIN_ORBIT_PITCH, MAX_ORBIT_PITCH);
+        view.ortho_half_height = view.ortho_half_height.clamp(MIN_ORTHO_HALF_HEIGHT, MAX_ORTHO_HALF_HEIGHT);
+        view
+    }
+
+    /// Move smoothly to a view, switching mode first if it differs
+    ///
+    /// Returns false and leaves the camera unchanged if the view contains
+    /// non-finite values or places the camera implausibly far away.
+    pub fn animate_to(&mut self, view: CameraView) -> bool {
+        if !view.is_finite()
+            || view.position.length() > MAX_RESTORED_DISTANCE
+            || view.target.length() > MAX_RESTORED_DISTANCE
+        {
+            return false;
+        }
+        self.set_mode(view.mode);
+        let to = self.clamp_view(view);
+        self.transition = Some(Transition {
+            from: self.view(),
+            to,
+            elapsed: 0.0,
+        });
+        true
+    }
+
+    /// Whether the camera is moving to a new view
+    pub fn is_animating(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Advance a running transition by `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+        transition.elapsed += dt;
+        let t = ease_in_out(transition.elapsed / TRANSITION_SECONDS);
+        let view = transition.from.lerp(&transition.to, t);
+        if transition.elapsed >= TRANSITION_SECONDS {
+            self.transition = None;
+        }
+        self.set_view(&view);
+    }
+
+    /// Animate to a view framing a sphere around `center`
+    ///
+    /// The perspective and orbit views keep their viewing direction and move
+    /// close enough for the sphere to fill the view with some margin; the
+    /// orthographic views center on it and zoom in.
+    pub fn focus_on(&mut self, center: Vec3, radius: f32) {
+        let mut view = self.view();
+        let extent = radius.max(0.1) * FOCUS_MARGIN;
+        let distance = extent / (self.fov * 0.5).tan();
+        match self.mode {
+            CameraMode::Perspective => {
+                let direction = (center - self.position).normalize_or(self.look_direction());
+                view.yaw = direction.x.atan2(direction.z);
+                view.pitch = direction.y.clamp(-1.0, 1.0).asin();
+                view.position = center - direction * distance;
+            }
+            CameraMode::Orbit => {
+                view.target = center;
+                view.orbit_distance = distance;
+            }
+            CameraMode::TopDown | CameraMode::Isometric => {
+                view.target = center;
+                view.ortho_half_height = extent;
+            }
+        }
+        self.animate_to(view);
+    }
+
+    /// World-space ray through a point given in normalized device
+    /// coordinates, as (origin, direction)
+    ///
+    /// Works for both projections: orthographic rays are parallel and start
+    /// on the near plane rather than at the eye.
+    pub fn screen_ray(&self, ndc_x: f32, ndc_y: f32) -> (Vec3, Vec3) {
+        let inv_view_proj = self.view_projection_matrix().inverse();
+        let unproject = |depth: f32| {
+            let point = inv_view_proj * Vec4::new(ndc_x, ndc_y, depth, 1.0);
+            point.truncate() / point.w
+        };
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+        (near, (far - near).normalize())
+    }
+
     /// Calculate yaw and pitch angles from camera position and look-at point
     fn calculate_angles_from_look_at(camera_pos: Vec3, look_at: Vec3) -> (f32, f32) {
         let direction = look_at - camera_pos;
@@ -87,14 +569,35 @@ impl Camera {
         );
 
         let target = self.position + direction;
-        let up = Vec3::Y;
+        // Looking straight down, the heading decides which way is up
+        let up = if self.mode == CameraMode::TopDown {
+            self.forward()
+        } else {
+            Vec3::Y
+        };
 
         Mat4::look_at_rh(self.position, target, up)
     }
 
     /// Get the projection matrix
     pub fn projection_matrix(&self) -> Mat4 {
-        Mat4::perspective_rh(self.fov, self.aspect, self.near, self.far)
+        if self.mode.is_orthographic() {
+            let half_height = self.ortho_half_height;
+            let half_width = half_height * self.aspect;
+            Mat4::orthographic_rh(-half_width, half_width, -half_height, half_height, -ORTHO_DEPTH, ORTHO_DEPTH)
+        } else {
+            Mat4::perspective_rh(self.fov, self.aspect, self.near, self.far)
+        }
+    }
+
+    /// Near and far planes of the current projection, and whether it is
+    /// orthographic, for turning depth buffer values back into distances
+    pub fn depth_range(&self) -> (f32, f32, bool) {
+        if self.mode.is_orthographic() {
+            (-ORTHO_DEPTH, ORTHO_DEPTH, true)
+        } else {
+            (self.near, self.far, false)
+        }
     }
 
     /// Get the combined view-projection matrix
@@ -107,13 +610,16 @@ impl Camera {
         self.aspect = aspect;
     }
 
-    /// Rotate camera based on mouse movement (pointer lock mode)
+    /// Rotate camera based on mouse movement, so the view turns the way the
+    /// mouse moves
     pub fn rotate(&mut self, delta_x: f32, delta_y: f32) {
+        self.transition = None;
         // Update yaw (horizontal) - clamped to limits
-        self.yaw = (self.yaw + delta_x * self.sensitivity)
+        self.yaw = (self.yaw - delta_x * self.sensitivity)
             .clamp(self.min_yaw, self.max_yaw);
 
         // Update pitch (vertical) - clamped to limits
+        let delta_y = if self.invert_y { -delta_y } else { delta_y };
         self.pitch = (self.pitch - delta_y * self.sensitivity)
             .clamp(self.min_pitch, self.max_pitch);
     }
@@ -137,13 +643,60 @@ impl Camera {
         ).normalize()
     }
 
-    /// Reset camera to default position and orientation
-    pub fn reset(&mut self) {
-        let config = &CONFIG.camera;
-        self.position = config.position;
-        self.yaw = self.default_yaw;
-        let (_, pitch) = Self::calculate_angles_from_look_at(config.position, config.look_at);
-        self.pitch = pitch;
+    /// Restore a saved perspective pose, clamping it to the camera limits
+    ///
+    /// Returns false and leaves the camera unchanged if the pose contains
+    /// non-finite values or places the camera implausibly far away.
+    pub fn apply_pose(&mut self, position: Vec3, yaw: f32, pitch: f32, fov_degrees: f32) -> bool {
+        if !position.is_finite() || !yaw.is_finite() || !pitch.is_finite() || !fov_degrees.is_finite() {
+            return false;
+        }
+        if position.length() > MAX_RESTORED_DISTANCE {
+            return false;
+        }
+
+        self.transition = None;
+        let pose = (
+            position,
+            yaw.clamp(self.min_yaw, self.max_yaw),
+            pitch.clamp(self.min_pitch, self.max_pitch),
+        );
+        if self.mode == CameraMode::Perspective {
+            (self.position, self.yaw, self.pitch) = pose;
+        } else {
+            self.perspective_pose = pose;
+        }
+        self.fov = fov_degrees.clamp(MIN_FOV_DEGREES, MAX_FOV_DEGREES).to_radians();
+        true
+    }
+
+    /// Apply changed camera settings (field of view, clipping planes and
+    /// mouse-look) without moving the camera
+    pub fn apply_config(&mut self, config: &CameraConfig) {
+        self.fov = config.fov.to_radians();
+        self.default_fov = self.fov;
+        self.sensitivity = LOOK_SPEED * config.mouse_sensitivity;
+        self.invert_y = config.invert_y;
+        self.near = config.near;
+        self.far = config.far;
+    }
+
+    /// Animate the active mode back to its default view
+    pub fn reset(&mut self, config: &CameraConfig) {
+        let mut view = self.view();
+        view.fov = config.fov;
+        if self.mode == CameraMode::Perspective {
+            let (_, pitch) = Self::calculate_angles_from_look_at(config.position, config.look_at);
+            view.position = config.position;
+            view.yaw = self.default_yaw;
+            view.pitch = pitch;
+        } else {
+            view.target = self.desk_frame.target;
+            view.orbit_distance = self.desk_frame.orbit_distance;
+            view.ortho_half_height = self.desk_frame.ortho_half_height;
+            view.orbit_yaw = self.default_yaw;
+        }
+        self.animate_to(view);
     }
 }
 
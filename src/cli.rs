@@ -0,0 +1,218 @@
+//! Command-line interface module
+//!
+//! Parses the simulator's command-line options and runs the subcommands that
+//! work on a desk without opening a window, so desks can be provisioned and
+//! checked from scripts.
+
+use crate::bundle::{export_bundle, import_bundle, ImportMode};
+use crate::config::Config;
+use crate::layouts::{LayoutEntry, LayoutManager};
+use crate::physics::PhysicsEngine;
+use crate::state::{self, AppState};
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// Command-line arguments
+#[derive(Debug, Parser)]
+#[command(name = "focus-desktop-simulator", version, about)]
+pub struct Cli {
+    /// Desk state file to use instead of the active layout's
+    #[arg(long, value_name = "FILE", global = true, conflicts_with = "layout")]
+    pub state: Option<PathBuf>,
+
+    /// Config file to use instead of the default `config.toml`
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Layout to open or operate on; opening it makes it the active layout
+    #[arg(long, value_name = "NAME", global = true)]
+    pub layout: Option<String>,
+
+    /// Initial window width in logical pixels
+    #[arg(long, value_name = "PX", requires = "height", value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: Option<u32>,
+
+    /// Initial window height in logical pixels
+    #[arg(long, value_name = "PX", requires = "width", value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: Option<u32>,
+
+    /// Start in borderless fullscreen
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Log filter, e.g. `debug` or `warn,focus_desktop_simulator=info`
+    /// (overrides RUST_LOG)
+    #[arg(long, value_name = "FILTER", global = true)]
+    pub log_level: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+/// Subcommands that run without opening a window
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// List the objects on the desk
+    List,
+    /// Export the desk to a bundle file
+    Export {
+        /// Bundle file to write
+        path: PathBuf,
+    },
+    /// Import a bundle file into the desk, creating the layout if needed
+    Import {
+        /// Bundle file to read
+        path: PathBuf,
+        /// Remove the existing objects first instead of merging
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Check the state file for problems without changing it
+    Validate,
+    /// Remove all objects from the desk (the previous file is kept as a backup)
+    Reset,
+}
+
+impl Cli {
+    /// Initial window size requested on the command line
+    pub fn window_size(&self) -> Option<(u32, u32)> {
+        self.width.zip(self.height)
+    }
+
+    /// Default log filter when neither `--log-level` nor RUST_LOG is set
+    ///
+    /// Subcommands only log warnings so their output stays readable.
+    pub fn default_log_filter(&self) -> &'static str {
+        if self.command.is_some() {
+            "warn"
+        } else {
+            "info"
+        }
+    }
+}
+
+/// Open the layout index in the data directory
+pub fn load_layouts() -> LayoutManager {
+    let data_dir = state::data_dir().unwrap_or_else(|| {
+        log::warn!("Could not determine data directory, using current directory");
+        PathBuf::from(".")
+    });
+    LayoutManager::load(data_dir)
+}
+
+/// State file selected by `--state` or `--layout`, falling back to the
+/// active layout
+fn target_path(cli: &Cli) -> Result<PathBuf, String> {
+    if let Some(path) = &cli.state {
+        return Ok(path.clone());
+    }
+    let layouts = load_layouts();
+    let Some(name) = &cli.layout else {
+        return Ok(layouts.active_path());
+    };
+    layouts.path_of(name).ok_or_else(|| {
+        format!(
+            "layout \"{}\" does not exist (available: {})",
+            name,
+            layouts.names().join(", ")
+        )
+    })
+}
+
+/// State file to import into, plus the layout to register once the import
+/// has been saved if `--layout` names one that does not exist yet
+fn import_target(cli: &Cli) -> Result<(PathBuf, Option<LayoutEntry>), String> {
+    let Some(name) = cli.layout.as_ref().filter(|_| cli.state.is_none()) else {
+        return Ok((target_path(cli)?, None));
+    };
+    let layouts = load_layouts();
+    if let Some(path) = layouts.path_of(name) {
+        return Ok((path, None));
+    }
+    let entry = layouts.prepare(name)?;
+    Ok((layouts.path_of_entry(&entry), Some(entry)))
+}
+
+/// Run a subcommand, returning an error message for the user on failure
+pub fn run(cli: &Cli, command: &CliCommand, config: &Config) -> Result<(), String> {
+    match command {
+        CliCommand::List => {
+            let state = AppState::read_from(target_path(cli)?);
+            println!("{:>6}  {:<14} {:>24}  {:>5}", "ID", "TYPE", "POSITION", "SCALE");
+            for obj in &state.objects {
+                let position = format!(
+                    "({:.2}, {:.2}, {:.2})",
+                    obj.position.x, obj.position.y, obj.position.z
+                );
+                println!(
+                    "{:>6}  {:<14} {:>24}  {:>5.2}",
+                    obj.id,
+                    obj.object_type.display_name(),
+                    position,
+                    obj.scale
+                );
+            }
+            println!("{} objects", state.objects.len());
+        }
+        CliCommand::Export { path } => {
+            let state = AppState::read_from(target_path(cli)?);
+            export_bundle(&state, path)?;
+            println!("Exported {} objects to {}", state.objects.len(), path.display());
+        }
+        CliCommand::Import { path, replace } => {
+            let (state_path, new_layout) = import_target(cli)?;
+            let mut state = AppState::load_from(state_path);
+            let mode = if *replace { ImportMode::Replace } else { ImportMode::Merge };
+            let report = import_bundle(&mut state, path, mode)?;
+            // Bring objects from another desk onto this one, as loading does
+            let mut physics = PhysicsEngine::new(config);
+            physics.collision_radius_multiplier = state.collision_radius_multiplier;
+            physics.collision_height_multiplier = state.collision_height_multiplier;
+            physics.settle_objects(&mut state.objects);
+            state.save().map_err(|e| format!("could not save state: {}", e))?;
+            if let Some(entry) = new_layout {
+                let name = entry.name.clone();
+                load_layouts().register(entry)?;
+                println!("Created layout \"{}\"", name);
+            }
+            println!("{}", report.summary());
+            for line in report.details() {
+                println!("  {}", line);
+            }
+        }
+        CliCommand::Validate => {
+            let path = target_path(cli)?;
+            if !path.exists() {
+                return Err(format!("no state file at {}", path.display()));
+            }
+            let (state, mut problems) = AppState::check_file(&path)?;
+            let physics = PhysicsEngine::new(config);
+            for obj in &state.objects {
+                if !physics.is_on_desk(obj.position) {
+                    problems.push(format!(
+                        "{} {} is off the desk at {}, would be moved back",
+                        obj.object_type.display_name(),
+                        obj.id,
+                        obj.position
+                    ));
+                }
+            }
+            if !problems.is_empty() {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                return Err(format!("{} problems found in {}", problems.len(), path.display()));
+            }
+            println!("{} is valid ({} objects)", path.display(), state.objects.len());
+        }
+        CliCommand::Reset => {
+            let path = target_path(cli)?;
+            let mut state = AppState::load_from(path.clone());
+            let removed = state.objects.len();
+            state.clear_objects();
+            state.save().map_err(|e| format!("could not save state: {}", e))?;
+            println!("Removed {} objects from {}", removed, path.display());
+        }
+    }
+    Ok(())
+}
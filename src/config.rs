@@ -1,10 +1,25 @@
 //! Configuration module for the Focus Desktop Simulator
 //!
-//! Contains all configurable parameters for the application.
+//! Contains all configurable parameters for the application. Values can be
+//! overridden by a `config.toml` file in the user's config directory; any
+//! setting left out of the file keeps its default. The app owns the loaded
+//! config and can change it at runtime from the settings panel.
 
 use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::bindings::{parse_bindings, Action};
+use crate::camera::{MAX_FOV_DEGREES, MIN_FOV_DEGREES};
+use crate::state::write_atomic;
+use crate::theme::DEFAULT_THEME_NAME;
 
 /// Camera configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CameraConfig {
     /// Field of view in degrees
     pub fov: f32,
@@ -16,6 +31,10 @@ pub struct CameraConfig {
     pub position: Vec3,
     /// Initial look-at target
     pub look_at: Vec3,
+    /// Mouse-look speed multiplier
+    pub mouse_sensitivity: f32,
+    /// Move the view down when the mouse moves up
+    pub invert_y: bool,
 }
 
 impl Default for CameraConfig {
@@ -26,71 +45,127 @@ impl Default for CameraConfig {
             far: 1000.0,
             position: Vec3::new(0.0, 4.5, 5.5),
             look_at: Vec3::new(0.0, 0.0, -1.5),
+            mouse_sensitivity: 1.0,
+            invert_y: false,
+        }
+    }
+}
+
+/// Outline of the desk surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeskShape {
+    #[default]
+    Rectangle,
+    /// A bar along the back with a return along the left side
+    LShape,
+    /// An ellipse filling the width and depth
+    Round,
+}
+
+impl DeskShape {
+    /// Names used in the config file
+    pub const NAMES: &'static [&'static str] = &["rectangle", "l-shape", "round"];
+
+    /// All shapes, in the order of `NAMES`
+    pub const ALL: &'static [DeskShape] = &[DeskShape::Rectangle, DeskShape::LShape, DeskShape::Round];
+
+    /// Display name for the settings window
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            DeskShape::Rectangle => "Rectangle",
+            DeskShape::LShape => "L-shaped",
+            DeskShape::Round => "Round",
         }
     }
 }
 
 /// Desk configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DeskConfig {
+    /// Outline of the desk surface
+    pub shape: DeskShape,
     /// Width of the desk surface
     pub width: f32,
     /// Depth of the desk surface
     pub depth: f32,
     /// Thickness of the desk surface
     pub height: f32,
+    /// Width of an L-shaped desk's return along the left side
+    pub arm_width: f32,
+    /// Depth of an L-shaped desk's bar along the back
+    pub arm_depth: f32,
     /// Color of the desk (RGB hex)
+    #[serde(with = "hex_color")]
     pub color: u32,
 }
 
 impl Default for DeskConfig {
     fn default() -> Self {
         Self {
+            shape: DeskShape::Rectangle,
             width: 10.0,
             depth: 7.0,
             height: 0.1,
+            arm_width: 3.5,
+            arm_depth: 3.0,
             color: 0x8b6914,
         }
     }
 }
 
 /// Physics configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PhysicsConfig {
     /// Height objects lift when dragged
     pub lift_height: f32,
-    /// Speed of object lifting
+    /// Speed of object lifting, as the rate per second at which the
+    /// remaining height closes
     pub lift_speed: f32,
-    /// Speed of object dropping
+    /// Speed of object dropping, as the rate per second at which the
+    /// remaining height closes
     pub drop_speed: f32,
-    /// Gravity constant
+    /// Gravity in units per second squared (pulls tilted objects upright or over)
     pub gravity: f32,
     /// Friction coefficient
     pub friction: f32,
     /// Bounce factor for collisions
     pub bounce_factor: f32,
+    /// Let thrown objects slide over the desk edge and fall to the floor
+    pub fall_off_desk: bool,
 }
 
 impl Default for PhysicsConfig {
     fn default() -> Self {
         Self {
             lift_height: 0.5,
-            lift_speed: 0.15,
-            drop_speed: 0.2,
-            gravity: 0.02,
+            lift_speed: 10.0,
+            drop_speed: 13.0,
+            gravity: 9.8,
             friction: 0.85,
             bounce_factor: 0.4,
+            fall_off_desk: false,
         }
     }
 }
 
 /// Color configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColorConfig {
     /// Background color (RGB hex)
+    #[serde(with = "hex_color")]
     pub background: u32,
     /// Ambient light color
+    #[serde(with = "hex_color")]
     pub ambient: u32,
     /// Directional light color
+    #[serde(with = "hex_color")]
     pub directional: u32,
     /// Ground/floor color
+    #[serde(with = "hex_color")]
     pub ground: u32,
 }
 
@@ -99,13 +174,40 @@ impl Default for ColorConfig {
         Self {
             background: 0x1a1a2e,
             ambient: 0x404060,
-            directional: 0xffffff,
+            directional: 0xccccbf,
             ground: 0x2d3748,
         }
     }
 }
 
+/// Scene lighting and fog configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightingConfig {
+    /// Direction pointing towards the directional light
+    pub direction: Vec3,
+    /// Distance from the camera where fog starts
+    pub fog_start: f32,
+    /// Distance over which fog fades in after `fog_start`
+    pub fog_range: f32,
+    /// Strongest fog blend (0 = no fog, 1 = fully background colored)
+    pub fog_max: f32,
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::new(0.5, 1.0, 0.3),
+            fog_start: 10.0,
+            fog_range: 40.0,
+            fog_max: 0.6,
+        }
+    }
+}
+
 /// Pixelation effect configuration (Signalis-style)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PixelationConfig {
     /// Whether pixelation effect is enabled
     pub enabled: bool,
@@ -120,7 +222,7 @@ pub struct PixelationConfig {
 impl Default for PixelationConfig {
     fn default() -> Self {
         Self {
-            enabled: true,
+            enabled: false,
             pixel_size: 4,
             normal_edge_strength: 0.3,
             depth_edge_strength: 0.4,
@@ -128,23 +230,706 @@ impl Default for PixelationConfig {
     }
 }
 
+impl PixelationConfig {
+    /// Reset out-of-range settings to their defaults, returning an error for
+    /// each
+    ///
+    /// Shared by the config file and theme files, which both have a
+    /// `[pixelation]` table.
+    pub fn validate(&mut self) -> Vec<ConfigError> {
+        let defaults = Self::default();
+        let mut errors = Vec::new();
+
+        for (field, value, default) in [
+            (
+                "pixelation.normal_edge_strength",
+                &mut self.normal_edge_strength,
+                defaults.normal_edge_strength,
+            ),
+            (
+                "pixelation.depth_edge_strength",
+                &mut self.depth_edge_strength,
+                defaults.depth_edge_strength,
+            ),
+        ] {
+            if !(0.0..=1.0).contains(value) {
+                errors.push(ConfigError::new(
+                    field,
+                    format!("must be in [0, 1] (got {}), using default {}", value, default),
+                ));
+                *value = default;
+            }
+        }
+
+        if !(1..=32).contains(&self.pixel_size) {
+            errors.push(ConfigError::new(
+                "pixelation.pixel_size",
+                format!(
+                    "must be between 1 and 32 (got {}), using default {}",
+                    self.pixel_size, defaults.pixel_size
+                ),
+            ));
+            self.pixel_size = defaults.pixel_size;
+        }
+
+        errors
+    }
+}
+
+/// Key and mouse bindings, e.g. `"Ctrl+Shift+Z"`; several bindings for one
+/// action are separated by commas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BindingsConfig {
+    /// Pick up and move an object (mouse button)
+    pub drag: String,
+    /// Customize an object, or toggle the palette over empty space (mouse
+    /// button)
+    pub context_click: String,
+    /// Rotate the held object (scroll)
+    pub rotate: String,
+    /// Scale the held object (scroll)
+    pub scale: String,
+    pub delete_object: String,
+    pub add_object: String,
+    pub cycle_type: String,
+    pub toggle_palette: String,
+    pub close_panels: String,
+    pub undo: String,
+    pub redo: String,
+    /// Orbit or pan the camera while held (mouse button)
+    pub camera_drag: String,
+    pub cycle_camera_mode: String,
+    pub rotate_view: String,
+    pub reset_view: String,
+    pub next_view_bookmark: String,
+    /// Go straight to the first, second, ... saved view
+    pub view_bookmark_1: String,
+    pub view_bookmark_2: String,
+    pub view_bookmark_3: String,
+    pub view_bookmark_4: String,
+    pub view_bookmark_5: String,
+    pub view_bookmark_6: String,
+    pub view_bookmark_7: String,
+    pub view_bookmark_8: String,
+    pub view_bookmark_9: String,
+}
+
+impl Default for BindingsConfig {
+    fn default() -> Self {
+        Self {
+            drag: "LeftMouse".to_string(),
+            context_click: "RightMouse".to_string(),
+            rotate: "Scroll".to_string(),
+            scale: "Shift+Scroll".to_string(),
+            delete_object: "Delete".to_string(),
+            add_object: "A".to_string(),
+            cycle_type: "T".to_string(),
+            toggle_palette: "M".to_string(),
+            close_panels: "Escape".to_string(),
+            undo: "Ctrl+Z".to_string(),
+            redo: "Ctrl+Shift+Z, Ctrl+Y".to_string(),
+            camera_drag: "MiddleMouse".to_string(),
+            cycle_camera_mode: "C".to_string(),
+            rotate_view: "R".to_string(),
+            reset_view: "Home".to_string(),
+            next_view_bookmark: "V".to_string(),
+            view_bookmark_1: "1".to_string(),
+            view_bookmark_2: "2".to_string(),
+            view_bookmark_3: "3".to_string(),
+            view_bookmark_4: "4".to_string(),
+            view_bookmark_5: "5".to_string(),
+            view_bookmark_6: "6".to_string(),
+            view_bookmark_7: "7".to_string(),
+            view_bookmark_8: "8".to_string(),
+            view_bookmark_9: "9".to_string(),
+        }
+    }
+}
+
+impl BindingsConfig {
+    /// Binding text of an action
+    pub fn get(&self, action: Action) -> &str {
+        match action {
+            Action::Drag => &self.drag,
+            Action::ContextClick => &self.context_click,
+            Action::Rotate => &self.rotate,
+            Action::Scale => &self.scale,
+            Action::DeleteObject => &self.delete_object,
+            Action::AddObject => &self.add_object,
+            Action::CycleType => &self.cycle_type,
+            Action::TogglePalette => &self.toggle_palette,
+            Action::ClosePanels => &self.close_panels,
+            Action::Undo => &self.undo,
+            Action::Redo => &self.redo,
+            Action::CameraDrag => &self.camera_drag,
+            Action::CycleCameraMode => &self.cycle_camera_mode,
+            Action::RotateView => &self.rotate_view,
+            Action::ResetView => &self.reset_view,
+            Action::NextViewBookmark => &self.next_view_bookmark,
+            Action::ViewBookmark(slot) => match slot {
+                0 => &self.view_bookmark_1,
+                1 => &self.view_bookmark_2,
+                2 => &self.view_bookmark_3,
+                3 => &self.view_bookmark_4,
+                4 => &self.view_bookmark_5,
+                5 => &self.view_bookmark_6,
+                6 => &self.view_bookmark_7,
+                7 => &self.view_bookmark_8,
+                _ => &self.view_bookmark_9,
+            },
+        }
+    }
+
+    /// Mutable binding text of an action
+    pub fn get_mut(&mut self, action: Action) -> &mut String {
+        match action {
+            Action::Drag => &mut self.drag,
+            Action::ContextClick => &mut self.context_click,
+            Action::Rotate => &mut self.rotate,
+            Action::Scale => &mut self.scale,
+            Action::DeleteObject => &mut self.delete_object,
+            Action::AddObject => &mut self.add_object,
+            Action::CycleType => &mut self.cycle_type,
+            Action::TogglePalette => &mut self.toggle_palette,
+            Action::ClosePanels => &mut self.close_panels,
+            Action::Undo => &mut self.undo,
+            Action::Redo => &mut self.redo,
+            Action::CameraDrag => &mut self.camera_drag,
+            Action::CycleCameraMode => &mut self.cycle_camera_mode,
+            Action::RotateView => &mut self.rotate_view,
+            Action::ResetView => &mut self.reset_view,
+            Action::NextViewBookmark => &mut self.next_view_bookmark,
+            Action::ViewBookmark(slot) => match slot {
+                0 => &mut self.view_bookmark_1,
+                1 => &mut self.view_bookmark_2,
+                2 => &mut self.view_bookmark_3,
+                3 => &mut self.view_bookmark_4,
+                4 => &mut self.view_bookmark_5,
+                5 => &mut self.view_bookmark_6,
+                6 => &mut self.view_bookmark_7,
+                7 => &mut self.view_bookmark_8,
+                _ => &mut self.view_bookmark_9,
+            },
+        }
+    }
+}
+
 /// Main configuration struct containing all settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Name of the selected visual theme
+    pub theme: String,
     pub camera: CameraConfig,
     pub desk: DeskConfig,
     pub physics: PhysicsConfig,
     pub colors: ColorConfig,
+    pub lighting: LightingConfig,
     pub pixelation: PixelationConfig,
+    pub bindings: BindingsConfig,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            theme: DEFAULT_THEME_NAME.to_string(),
             camera: CameraConfig::default(),
             desk: DeskConfig::default(),
             physics: PhysicsConfig::default(),
             colors: ColorConfig::default(),
+            lighting: LightingConfig::default(),
             pixelation: PixelationConfig::default(),
+            bindings: BindingsConfig::default(),
+        }
+    }
+}
+
+/// A problem with a single setting in the config file
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    /// Dotted path of the setting, e.g. `desk.width`
+    pub field: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl ConfigError {
+    pub(crate) fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl Config {
+    /// Load the user config file merged over the defaults
+    ///
+    /// Problems with individual settings are logged and those settings keep
+    /// their default values. A missing file simply yields the defaults.
+    pub fn load() -> Self {
+        let Some(path) = config_file_path() else {
+            log::warn!("Could not determine config directory, using default config");
+            return Self::default();
+        };
+        if !path.exists() {
+            return Self::default();
+        }
+
+        match Self::load_from(&path) {
+            Ok((config, errors)) => {
+                for error in &errors {
+                    log::warn!("Config {:?}: {}", path, error);
+                }
+                log::info!("Loaded config from {:?}", path);
+                config
+            }
+            Err(e) => {
+                log::warn!("Could not load config {:?}: {}. Using defaults.", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Load a config file, returning the effective config and every setting
+    /// that was rejected
+    pub fn load_from(path: &Path) -> Result<(Self, Vec<ConfigError>), String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_toml_str(&content)
+    }
+
+    /// Parse TOML config content and merge it over the defaults
+    pub fn from_toml_str(content: &str) -> Result<(Self, Vec<ConfigError>), String> {
+        let user: toml::Table = content.parse().map_err(|e| format!("invalid TOML: {}", e))?;
+
+        let mut merged = toml::Table::try_from(Self::default()).map_err(|e| e.to_string())?;
+        let mut errors = Vec::new();
+        merge_table(&mut merged, &user, "", &mut errors);
+
+        let mut config: Self = merged.try_into().map_err(|e| e.to_string())?;
+        errors.extend(config.validate());
+        Ok((config, errors))
+    }
+
+    /// Serialize the config as TOML
+    pub fn to_toml_string(&self) -> Result<String, String> {
+        let mut table = toml::Table::try_from(self).map_err(|e| e.to_string())?;
+        round_floats(&mut table);
+        toml::to_string(&table).map_err(|e| e.to_string())
+    }
+
+    /// Write the effective config so it can be edited
+    ///
+    /// Goes to `config.toml` if the user has none yet, otherwise next to it
+    /// as `config.effective.toml` so hand-written files are never overwritten.
+    pub fn write_effective(&self) -> Result<PathBuf, String> {
+        let mut path = config_file_path().ok_or("could not determine config directory")?;
+        if path.exists() {
+            path.set_file_name("config.effective.toml");
+        }
+        fs::write(&path, self.to_toml_string()?).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    /// Save the config to `config.toml`, replacing the file's contents
+    ///
+    /// Used by the settings panel; comments in a hand-written file are not
+    /// kept.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = config_file_path().ok_or("could not determine config directory")?;
+        write_atomic(&path, self.to_toml_string()?.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    /// Reset settings with out-of-range values to their defaults, returning
+    /// an error for each
+    fn validate(&mut self) -> Vec<ConfigError> {
+        let defaults = Self::default();
+        let mut errors = Vec::new();
+
+        let mut check = |field: &str, value: &mut f32, default: f32, valid: bool, rule: &str| {
+            if !valid || !value.is_finite() {
+                errors.push(ConfigError::new(
+                    field,
+                    format!("{} (got {}), using default {}", rule, value, default),
+                ));
+                *value = default;
+            }
+        };
+
+        let camera = &mut self.camera;
+        let fov = camera.fov;
+        check(
+            "camera.fov",
+            &mut camera.fov,
+            defaults.camera.fov,
+            (MIN_FOV_DEGREES..=MAX_FOV_DEGREES).contains(&fov),
+            "must be between 20 and 120 degrees",
+        );
+        let near = camera.near;
+        check(
+            "camera.near",
+            &mut camera.near,
+            defaults.camera.near,
+            near > 0.0,
+            "must be greater than 0",
+        );
+        let far = camera.far;
+        check(
+            "camera.far",
+            &mut camera.far,
+            defaults.camera.far,
+            far > camera.near,
+            "must be greater than camera.near",
+        );
+        let sensitivity = camera.mouse_sensitivity;
+        check(
+            "camera.mouse_sensitivity",
+            &mut camera.mouse_sensitivity,
+            defaults.camera.mouse_sensitivity,
+            sensitivity > 0.0 && sensitivity <= 10.0,
+            "must be between 0 and 10",
+        );
+
+        let desk = &mut self.desk;
+        let width = desk.width;
+        check(
+            "desk.width",
+            &mut desk.width,
+            defaults.desk.width,
+            width > 0.0 && width <= 100.0,
+            "must be between 0 and 100",
+        );
+        let depth = desk.depth;
+        check(
+            "desk.depth",
+            &mut desk.depth,
+            defaults.desk.depth,
+            depth > 0.0 && depth <= 100.0,
+            "must be between 0 and 100",
+        );
+        let height = desk.height;
+        check(
+            "desk.height",
+            &mut desk.height,
+            defaults.desk.height,
+            height > 0.0,
+            "must be greater than 0",
+        );
+        let arm_width = desk.arm_width;
+        check(
+            "desk.arm_width",
+            &mut desk.arm_width,
+            defaults.desk.arm_width,
+            arm_width > 0.0 && arm_width <= desk.width,
+            "must be between 0 and desk.width",
+        );
+        let arm_depth = desk.arm_depth;
+        check(
+            "desk.arm_depth",
+            &mut desk.arm_depth,
+            defaults.desk.arm_depth,
+            arm_depth > 0.0 && arm_depth <= desk.depth,
+            "must be between 0 and desk.depth",
+        );
+
+        let physics = &mut self.physics;
+        let lift_height = physics.lift_height;
+        check(
+            "physics.lift_height",
+            &mut physics.lift_height,
+            defaults.physics.lift_height,
+            lift_height >= 0.0,
+            "must not be negative",
+        );
+        let lift_speed = physics.lift_speed;
+        check(
+            "physics.lift_speed",
+            &mut physics.lift_speed,
+            defaults.physics.lift_speed,
+            (1.0..=100.0).contains(&lift_speed),
+            "must be between 1 and 100 (per second)",
+        );
+        let drop_speed = physics.drop_speed;
+        check(
+            "physics.drop_speed",
+            &mut physics.drop_speed,
+            defaults.physics.drop_speed,
+            (1.0..=100.0).contains(&drop_speed),
+            "must be between 1 and 100 (per second)",
+        );
+        let gravity = physics.gravity;
+        check(
+            "physics.gravity",
+            &mut physics.gravity,
+            defaults.physics.gravity,
+            (1.0..=100.0).contains(&gravity),
+            "must be between 1 and 100 (units per second squared)",
+        );
+        let friction = physics.friction;
+        check(
+            "physics.friction",
+            &mut physics.friction,
+            defaults.physics.friction,
+            (0.0..=1.0).contains(&friction),
+            "must be in [0, 1]",
+        );
+        let bounce = physics.bounce_factor;
+        check(
+            "physics.bounce_factor",
+            &mut physics.bounce_factor,
+            defaults.physics.bounce_factor,
+            (0.0..=1.0).contains(&bounce),
+            "must be in [0, 1]",
+        );
+
+        let lighting = &mut self.lighting;
+        let fog_start = lighting.fog_start;
+        check(
+            "lighting.fog_start",
+            &mut lighting.fog_start,
+            defaults.lighting.fog_start,
+            fog_start >= 0.0,
+            "must not be negative",
+        );
+        let fog_range = lighting.fog_range;
+        check(
+            "lighting.fog_range",
+            &mut lighting.fog_range,
+            defaults.lighting.fog_range,
+            fog_range > 0.0,
+            "must be greater than 0",
+        );
+        let fog_max = lighting.fog_max;
+        check(
+            "lighting.fog_max",
+            &mut lighting.fog_max,
+            defaults.lighting.fog_max,
+            (0.0..=1.0).contains(&fog_max),
+            "must be in [0, 1]",
+        );
+
+        errors.extend(self.pixelation.validate());
+
+        for (field, value, default) in [
+            ("camera.position", &mut self.camera.position, defaults.camera.position),
+            ("camera.look_at", &mut self.camera.look_at, defaults.camera.look_at),
+            ("lighting.direction", &mut self.lighting.direction, defaults.lighting.direction),
+        ] {
+            if !value.is_finite() {
+                errors.push(ConfigError::new(field, "must be finite, using default"));
+                *value = default;
+            }
+        }
+        if self.lighting.direction.length() < 1e-3 {
+            errors.push(ConfigError::new("lighting.direction", "must not be zero, using default"));
+            self.lighting.direction = defaults.lighting.direction;
+        }
+
+        for &action in Action::ALL {
+            if let Err(e) = parse_bindings(action, self.bindings.get(action)) {
+                let default = defaults.bindings.get(action);
+                errors.push(ConfigError::new(
+                    format!("bindings.{}", action.config_key()),
+                    format!("{}, using default \"{}\"", e, default),
+                ));
+                *self.bindings.get_mut(action) = default.to_string();
+            }
+        }
+
+        errors
+    }
+}
+
+/// Config file chosen on the command line, used instead of the default
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use the given file as the config file for the rest of the run
+///
+/// Must be called before the config is first loaded. User themes are then
+/// looked up next to this file as well.
+pub fn set_config_file_path(path: PathBuf) {
+    if CONFIG_PATH_OVERRIDE.set(path).is_err() {
+        log::warn!("Config file path was already set, ignoring override");
+    }
+}
+
+/// Path of the user config file
+pub fn config_file_path() -> Option<PathBuf> {
+    if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+        return Some(path.clone());
+    }
+    dirs::config_dir().map(|mut path| {
+        path.push("focus-desktop-simulator");
+        fs::create_dir_all(&path).ok();
+        path.push("config.toml");
+        path
+    })
+}
+
+/// Merge user settings into the default table, keeping the default for any
+/// setting that is unknown or has the wrong type
+fn merge_table(base: &mut toml::Table, user: &toml::Table, prefix: &str, errors: &mut Vec<ConfigError>) {
+    for (key, value) in user {
+        let field = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        let Some(existing) = base.get_mut(key) else {
+            errors.push(ConfigError::new(field, "unknown setting, ignored"));
+            continue;
+        };
+
+        match (existing, value) {
+            (toml::Value::Table(base), toml::Value::Table(user)) => {
+                merge_table(base, user, &field, errors);
+            }
+            (toml::Value::Table(_), _) => {
+                errors.push(ConfigError::new(field, "expected a table, ignored"));
+            }
+            (_, toml::Value::String(text))
+                if allowed_values(&field).is_some_and(|allowed| !allowed.contains(&text.as_str())) =>
+            {
+                errors.push(ConfigError::new(
+                    &field,
+                    format!(
+                        "expected one of {}, got \"{}\", using default",
+                        allowed_values(&field).unwrap_or_default().join(", "),
+                        text
+                    ),
+                ));
+            }
+            (existing, value) => match coerce_setting(existing, value) {
+                Some(value) => *existing = value,
+                None => errors.push(ConfigError::new(
+                    field,
+                    format!(
+                        "expected {}, got {}, using default",
+                        expected_kind(existing),
+                        value.type_str()
+                    ),
+                )),
+            },
+        }
+    }
+}
+
+/// Accepted values of settings that are a fixed set of names
+fn allowed_values(field: &str) -> Option<&'static [&'static str]> {
+    match field {
+        "desk.shape" => Some(DeskShape::NAMES),
+        _ => None,
+    }
+}
+
+/// Convert a user value to the type of the default value, if compatible
+fn coerce_setting(default: &toml::Value, value: &toml::Value) -> Option<toml::Value> {
+    use toml::Value;
+    match (default, value) {
+        (Value::Float(_), Value::Float(_)) => Some(value.clone()),
+        (Value::Float(_), Value::Integer(i)) => Some(Value::Float(*i as f64)),
+        // Integer settings are `u32`; anything wider would fail the final
+        // conversion and throw away the whole file
+        (Value::Integer(_), Value::Integer(i)) if u32::try_from(*i).is_ok() => Some(value.clone()),
+        (Value::Boolean(_), Value::Boolean(_)) => Some(value.clone()),
+        // Strings whose default is a hex color must be colors too
+        (Value::String(d), Value::String(text)) if hex_color::parse(d).is_some() => {
+            hex_color::parse(text).map(|c| Value::String(hex_color::format(c)))
+        }
+        (Value::String(d), Value::Integer(i))
+            if hex_color::parse(d).is_some() && (0..=0xFFFFFF).contains(i) =>
+        {
+            Some(Value::String(hex_color::format(*i as u32)))
+        }
+        (Value::String(_), Value::String(_)) => Some(value.clone()),
+        (Value::Array(defaults), Value::Array(items)) if defaults.len() == items.len() => defaults
+            .iter()
+            .zip(items)
+            .map(|(d, v)| coerce_setting(d, v))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+        _ => None,
+    }
+}
+
+/// Print floats with f32 precision so written configs show `0.1`, not
+/// `0.10000000149011612`
+pub(crate) fn round_floats(table: &mut toml::Table) {
+    fn round(value: &mut toml::Value) {
+        match value {
+            toml::Value::Float(f) => {
+                *f = (*f as f32).to_string().parse().unwrap_or(*f);
+            }
+            toml::Value::Array(items) => items.iter_mut().for_each(round),
+            toml::Value::Table(table) => round_floats(table),
+            _ => {}
+        }
+    }
+    table.iter_mut().for_each(|(_, value)| round(value));
+}
+
+/// Human-readable kind of a default setting for error messages
+fn expected_kind(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(d) if hex_color::parse(d).is_some() => "a color like \"#1a1a2e\"".to_string(),
+        toml::Value::Array(items) => format!("an array of {} numbers", items.len()),
+        toml::Value::Integer(_) => format!("a whole number from 0 to {}", u32::MAX),
+        other => format!("a {}", other.type_str()),
+    }
+}
+
+/// Serde helpers for colors written as `"#rrggbb"` strings (plain integers
+/// are accepted too)
+pub(crate) mod hex_color {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Text(String),
+        Number(u32),
+    }
+
+    pub fn parse(text: &str) -> Option<u32> {
+        let digits = text.trim().trim_start_matches('#');
+        if digits.len() != 6 {
+            return None;
+        }
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    pub fn format(color: u32) -> String {
+        format!("#{:06x}", color & 0xFFFFFF)
+    }
+
+    pub fn serialize<S>(color: &u32, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format(*color))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Repr::deserialize(deserializer)? {
+            Repr::Text(text) => parse(&text)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid color \"{}\"", text))),
+            Repr::Number(color) if color <= 0xFFFFFF => Ok(color),
+            Repr::Number(color) => Err(serde::de::Error::custom(format!(
+                "color {:#x} is out of range",
+                color
+            ))),
         }
     }
 }
@@ -163,5 +948,27 @@ pub fn hex_to_rgba(hex: u32) -> [f32; 4] {
     [r, g, b, 1.0]
 }
 
-/// Global configuration instance
-pub static CONFIG: std::sync::LazyLock<Config> = std::sync::LazyLock::new(Config::default);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_integer_rejects_only_that_setting() {
+        let (config, errors) = Config::from_toml_str(
+            "[pixelation]\npixel_size = 5000000000\n\n[desk]\nwidth = 20.0\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.pixelation.pixel_size, PixelationConfig::default().pixel_size);
+        assert_eq!(config.desk.width, 20.0);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "pixelation.pixel_size");
+    }
+
+    #[test]
+    fn negative_integer_is_rejected() {
+        let (config, errors) = Config::from_toml_str("[pixelation]\npixel_size = -2\n").unwrap();
+        assert_eq!(config.pixelation.pixel_size, PixelationConfig::default().pixel_size);
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,150 @@
+//! Desk geometry module
+//!
+//! Derives everything that depends on the desk's shape and size from the
+//! desk settings: bounds, placement and clamping, and the outline used to
+//! build the desk mesh.
+
+use crate::config::{DeskConfig, DeskShape};
+use glam::Vec3;
+use std::f32::consts::TAU;
+
+/// Number of edges used to approximate a round desk
+const ROUND_SEGMENTS: usize = 48;
+
+/// Axis-aligned rectangle on the desk plane (min x, max x, min z, max z)
+pub type Rect = (f32, f32, f32, f32);
+
+/// Desk surface geometry
+#[derive(Debug, Clone, PartialEq)]
+pub struct Desk {
+    pub shape: DeskShape,
+    /// Half of the overall width (x)
+    pub half_width: f32,
+    /// Half of the overall depth (z)
+    pub half_depth: f32,
+    /// Y position of the desk surface
+    pub surface_y: f32,
+    /// Width of the L-shaped desk's return along the left side
+    pub arm_width: f32,
+    /// Depth of the L-shaped desk's bar along the back
+    pub arm_depth: f32,
+}
+
+impl Desk {
+    /// Build the desk geometry from the desk settings
+    pub fn from_config(config: &DeskConfig) -> Self {
+        Self {
+            shape: config.shape,
+            half_width: config.width / 2.0,
+            half_depth: config.depth / 2.0,
+            surface_y: config.height,
+            arm_width: config.arm_width.min(config.width),
+            arm_depth: config.arm_depth.min(config.depth),
+        }
+    }
+
+    /// Bounding rectangle of the desk surface
+    pub fn bounds(&self) -> Rect {
+        (-self.half_width, self.half_width, -self.half_depth, self.half_depth)
+    }
+
+    /// The desk surface as non-overlapping rectangles (rectangular and
+    /// L-shaped desks only)
+    pub fn rects(&self) -> Vec<Rect> {
+        let (min_x, max_x, min_z, max_z) = self.bounds();
+        match self.shape {
+            DeskShape::Rectangle | DeskShape::Round => vec![self.bounds()],
+            DeskShape::LShape => {
+                let back_z = min_z + self.arm_depth;
+                vec![
+                    // Bar along the back edge
+                    (min_x, max_x, min_z, back_z),
+                    // Return running forward along the left edge
+                    (min_x, min_x + self.arm_width, back_z, max_z),
+                ]
+            }
+        }
+    }
+
+    /// Move a position so a footprint of the given radius lies on the desk
+    ///
+    /// Footprints larger than the desk are centered on it.
+    pub fn clamp(&self, position: Vec3, radius: f32) -> Vec3 {
+        match self.shape {
+            DeskShape::Round => {
+                let a = (self.half_width - radius).max(0.0);
+                let b = (self.half_depth - radius).max(0.0);
+                let nx = if a > 0.0 { position.x / a } else { 0.0 };
+                let nz = if b > 0.0 { position.z / b } else { 0.0 };
+                let k = nx * nx + nz * nz;
+                if k <= 1.0 {
+                    return Vec3::new(nx * a, position.y, nz * b);
+                }
+                let scale = 1.0 / k.sqrt();
+                Vec3::new(nx * a * scale, position.y, nz * b * scale)
+            }
+            _ => self
+                .rects()
+                .into_iter()
+                .map(|rect| clamp_to_rect(position, rect, radius))
+                .min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)))
+                .unwrap_or(position),
+        }
+    }
+
+    /// Random point on the desk surface where a footprint of the given radius
+    /// fits
+    pub fn random_position(&self, radius: f32) -> Vec3 {
+        let (min_x, max_x, min_z, max_z) = self.bounds();
+        let mut candidate = Vec3::new(0.0, self.surface_y, 0.0);
+        for _ in 0..32 {
+            candidate = Vec3::new(
+                min_x + rand::random::<f32>() * (max_x - min_x),
+                self.surface_y,
+                min_z + rand::random::<f32>() * (max_z - min_z),
+            );
+            let clamped = self.clamp(candidate, radius);
+            if (clamped - candidate).length_squared() < 1e-6 {
+                return clamped;
+            }
+        }
+        self.clamp(candidate, radius)
+    }
+
+    /// Outline of the desk surface, counter-clockwise when seen from above
+    pub fn outline(&self) -> Vec<(f32, f32)> {
+        let (min_x, max_x, min_z, max_z) = self.bounds();
+        match self.shape {
+            DeskShape::Rectangle => vec![(min_x, max_z), (max_x, max_z), (max_x, min_z), (min_x, min_z)],
+            DeskShape::LShape => {
+                let inner_x = min_x + self.arm_width;
+                let inner_z = min_z + self.arm_depth;
+                vec![
+                    (min_x, max_z),
+                    (inner_x, max_z),
+                    (inner_x, inner_z),
+                    (max_x, inner_z),
+                    (max_x, min_z),
+                    (min_x, min_z),
+                ]
+            }
+            DeskShape::Round => (0..ROUND_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / ROUND_SEGMENTS as f32 * TAU;
+                    (angle.cos() * self.half_width, -angle.sin() * self.half_depth)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Clamp a footprint's center into a rectangle shrunk by its radius
+fn clamp_to_rect(position: Vec3, (min_x, max_x, min_z, max_z): Rect, radius: f32) -> Vec3 {
+    let radius_x = radius.min((max_x - min_x) / 2.0);
+    let radius_z = radius.min((max_z - min_z) / 2.0);
+    Vec3::new(
+        position.x.clamp(min_x + radius_x, max_x - radius_x),
+        position.y,
+        position.z.clamp(min_z + radius_z, max_z - radius_z),
+    )
+}
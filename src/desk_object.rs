@@ -2,9 +2,19 @@
 //!
 //! Defines the various objects that can be placed on the desk.
 
-use glam::{Vec3, Quat};
+use crate::physics::{Footprint, ObjectPhysicsState};
+use glam::{Mat4, Vec3, Quat};
 use serde::{Deserialize, Serialize};
 
+/// Smallest allowed object scale
+pub const MIN_SCALE: f32 = 0.3;
+
+/// Largest allowed object scale
+pub const MAX_SCALE: f32 = 3.0;
+
+/// Stability at or above which an object never tips over
+const STABLE_THRESHOLD: f32 = 0.9;
+
 /// Type of desk object
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
@@ -238,6 +248,28 @@ impl ObjectType {
         }
     }
 
+    /// Get the footprint shape for the object type at scale 1, matching its
+    /// mesh
+    pub fn collision_shape(&self) -> CollisionShape {
+        match self {
+            ObjectType::Clock => CollisionShape::Cylinder { radius: 0.25 },
+            ObjectType::Lamp => CollisionShape::Cylinder { radius: 0.15 },
+            ObjectType::Plant => CollisionShape::Cylinder { radius: 0.15 },
+            ObjectType::Coffee => CollisionShape::Cylinder { radius: 0.1 },
+            ObjectType::Laptop => CollisionShape::Box { half_width: 0.2, half_depth: 0.15 },
+            ObjectType::Notebook => CollisionShape::Box { half_width: 0.125, half_depth: 0.175 },
+            ObjectType::PenHolder => CollisionShape::Cylinder { radius: 0.08 },
+            ObjectType::Books => CollisionShape::Box { half_width: 0.12, half_depth: 0.16 },
+            ObjectType::PhotoFrame => CollisionShape::Box { half_width: 0.1, half_depth: 0.06 },
+            ObjectType::Globe => CollisionShape::Cylinder { radius: 0.12 },
+            ObjectType::Trophy => CollisionShape::Cylinder { radius: 0.1 },
+            ObjectType::Hourglass => CollisionShape::Box { half_width: 0.05, half_depth: 0.05 },
+            ObjectType::Metronome => CollisionShape::Box { half_width: 0.06, half_depth: 0.05 },
+            ObjectType::Paper => CollisionShape::Box { half_width: 0.105, half_depth: 0.1485 },
+            ObjectType::Magazine => CollisionShape::Box { half_width: 0.11, half_depth: 0.15 },
+        }
+    }
+
     /// Get all object types for the palette
     pub fn all() -> &'static [ObjectType] {
         &[
@@ -277,6 +309,55 @@ pub struct ObjectPhysics {
     pub no_stacking_on_top: bool,
 }
 
+impl ObjectPhysics {
+    /// Whether the object can wobble or tip over at all (flat, stable
+    /// objects like notebooks never do)
+    pub fn can_tip(&self) -> bool {
+        self.stability < STABLE_THRESHOLD
+    }
+}
+
+/// Footprint of an object on the desk, before rotation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionShape {
+    /// Round objects such as mugs, pen holders and lamp bases
+    Cylinder { radius: f32 },
+    /// Flat or boxy objects, measured along the object's own X and Z axes
+    Box { half_width: f32, half_depth: f32 },
+}
+
+impl CollisionShape {
+    /// The shape enlarged by `factor`
+    pub fn scaled(self, factor: f32) -> Self {
+        match self {
+            CollisionShape::Cylinder { radius } => CollisionShape::Cylinder { radius: radius * factor },
+            CollisionShape::Box { half_width, half_depth } => CollisionShape::Box {
+                half_width: half_width * factor,
+                half_depth: half_depth * factor,
+            },
+        }
+    }
+
+    /// The shape with `margin` added on every side
+    pub fn grown(self, margin: f32) -> Self {
+        match self {
+            CollisionShape::Cylinder { radius } => CollisionShape::Cylinder { radius: radius + margin },
+            CollisionShape::Box { half_width, half_depth } => CollisionShape::Box {
+                half_width: half_width + margin,
+                half_depth: half_depth + margin,
+            },
+        }
+    }
+
+    /// Radius of the smallest circle around the shape, whatever its rotation
+    pub fn bounding_radius(&self) -> f32 {
+        match *self {
+            CollisionShape::Cylinder { radius } => radius,
+            CollisionShape::Box { half_width, half_depth } => half_width.hypot(half_depth),
+        }
+    }
+}
+
 /// A desk object instance
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeskObject {
@@ -316,6 +397,9 @@ pub struct DeskObject {
     /// Original Y position (on desk surface)
     #[serde(skip)]
     pub original_y: f32,
+    /// Sliding and spinning motion
+    #[serde(skip)]
+    pub physics_state: ObjectPhysicsState,
 }
 
 // Default value functions for serde
@@ -366,60 +450,86 @@ impl DeskObject {
             is_dragging: false,
             target_y: y,
             original_y: y,
+            physics_state: ObjectPhysicsState::default(),
         }
     }
 
-    /// Get the model matrix for this object
-    pub fn model_matrix(&self) -> glam::Mat4 {
-        glam::Mat4::from_scale_rotation_translation(
+    /// A copy of this object posed between an earlier copy of itself and
+    /// now, for drawing between physics steps
+    pub fn interpolated_from(&self, previous: &DeskObject, alpha: f32) -> DeskObject {
+        let mut object = self.clone();
+        object.position = previous.position.lerp(self.position, alpha);
+        object.rotation = previous.rotation.slerp(self.rotation, alpha);
+        object.physics_state.tilt = previous.physics_state.tilt.lerp(self.physics_state.tilt, alpha);
+        object
+    }
+
+    /// Get the model matrix for this object, tipped over the edge of its
+    /// base by its tilt
+    pub fn model_matrix(&self) -> Mat4 {
+        let upright = Mat4::from_scale_rotation_translation(
             Vec3::splat(self.scale),
             self.rotation,
             self.position,
-        )
+        );
+        let tilt = self.physics_state.tilt;
+        let angle = tilt.length();
+        if angle < 1e-4 {
+            return upright;
+        }
+
+        let lean = Vec3::new(-tilt.z, 0.0, tilt.x) / angle;
+        let base = self.position - Vec3::Y * (self.object_type.physics().base_offset * self.scale);
+        let pivot = base + lean * self.footprint(1.0).extent_along(lean);
+        Mat4::from_translation(pivot)
+            * Mat4::from_quat(Quat::from_scaled_axis(tilt))
+            * Mat4::from_translation(-pivot)
+            * upright
     }
 
-    /// Get the collision radius for this object
+    /// Get the collision radius for this object: the radius of a circle
+    /// around its footprint in any rotation
     pub fn collision_radius(&self) -> f32 {
-        let physics = self.object_type.physics();
-        // Base radius based on object type, scaled
-        let base_radius = match self.object_type {
-            ObjectType::Lamp => 0.3,
-            ObjectType::Plant => 0.25,
-            ObjectType::Coffee => 0.15,
-            ObjectType::Laptop => 0.5,
-            ObjectType::Notebook => 0.35,
-            ObjectType::Books => 0.3,
-            ObjectType::Globe => 0.25,
-            ObjectType::Trophy => 0.2,
-            _ => 0.2,
-        };
-        base_radius * self.scale * self.collision_radius_multiplier
+        self.object_type.collision_shape().bounding_radius() * self.scale * self.collision_radius_multiplier
     }
 
-    /// Get the collision height for this object
+    /// Get the object's footprint on the desk, following its rotation and
+    /// scale, with the global radius multiplier applied on top of its own
+    pub fn footprint(&self, radius_multiplier: f32) -> Footprint {
+        let shape = self
+            .object_type
+            .collision_shape()
+            .scaled(self.scale * self.collision_radius_multiplier * radius_multiplier);
+        Footprint::new(self.position, shape, self.rotation)
+    }
+
+    /// Get the collision height for this object, lower when it is tilted
     pub fn collision_height(&self) -> f32 {
         let physics = self.object_type.physics();
-        physics.height * self.scale * self.collision_height_multiplier
+        let upright = physics.height * self.scale * self.collision_height_multiplier;
+        let tilt = self.physics_state.tilt;
+        let angle = tilt.length();
+        if angle == 0.0 {
+            return upright;
+        }
+        // Tipped over its base edge, the top comes down as the far side of
+        // the base rises
+        let lean = Vec3::new(-tilt.z, 0.0, tilt.x) / angle;
+        upright * angle.cos() + 2.0 * self.footprint(1.0).extent_along(lean) * angle.sin()
     }
 
     /// Check if a point is inside the collision bounds
     pub fn contains_point(&self, point: Vec3) -> bool {
-        let radius = self.collision_radius();
         let height = self.collision_height();
-
-        let dx = point.x - self.position.x;
-        let dz = point.z - self.position.z;
-        let horizontal_dist_sq = dx * dx + dz * dz;
-
-        let within_radius = horizontal_dist_sq <= radius * radius;
+        let within_footprint = self.footprint(1.0).contains(point);
         let within_height = point.y >= self.position.y && point.y <= self.position.y + height;
 
-        within_radius && within_height
+        within_footprint && within_height
     }
 }
 
 // Custom serialization for Vec3
-mod vec3_serde {
+pub(crate) mod vec3_serde {
     use glam::Vec3;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
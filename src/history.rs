@@ -0,0 +1,372 @@
+//! Undo/redo history module
+//!
+//! Every desk edit is recorded as an invertible command so it can be undone
+//! and redone. Continuous edits such as scroll-wheel rotation are coalesced
+//! into a single step.
+
+use crate::desk_object::DeskObject;
+use crate::physics::ObjectPhysicsState;
+use crate::state::AppState;
+use glam::{Quat, Vec3};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Maximum number of undo steps kept
+const HISTORY_LIMIT: usize = 100;
+
+/// Edits of the same kind on the same object closer together than this are
+/// merged into one step
+const COALESCE_WINDOW: Duration = Duration::from_millis(600);
+
+/// An invertible desk edit
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// An object was added
+    Add(DeskObject),
+    /// An object was removed from the given list index
+    Remove { object: DeskObject, index: usize },
+    /// An object was moved to a new resting position
+    Move { id: u64, from: Vec3, to: Vec3 },
+    /// An object was rotated
+    Rotate { id: u64, from: Quat, to: Quat },
+    /// An object was scaled
+    Scale { id: u64, from: f32, to: f32 },
+    /// An object's main and accent colors changed
+    Recolor { id: u64, from: (u32, u32), to: (u32, u32) },
+    /// The whole object list was replaced (clear all, import)
+    Replace { before: Vec<DeskObject>, after: Vec<DeskObject> },
+    /// Several edits made at once, e.g. a thrown object and those it pushed
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    /// Apply the command forwards (redo) or backwards (undo)
+    fn apply(&self, state: &mut AppState, forward: bool) {
+        match self {
+            Command::Add(object) => {
+                if forward {
+                    state.add_object(object.clone());
+                } else {
+                    state.remove_object(object.id);
+                }
+            }
+            Command::Remove { object, index } => {
+                if forward {
+                    state.remove_object(object.id);
+                } else {
+                    state.insert_object(*index, object.clone());
+                }
+            }
+            Command::Move { id, from, to } => {
+                let position = if forward { *to } else { *from };
+                if let Some(obj) = state.get_object_mut(*id) {
+                    obj.position = position;
+                    obj.target_y = position.y;
+                    obj.original_y = position.y;
+                    obj.physics_state = ObjectPhysicsState::default();
+                }
+            }
+            Command::Rotate { id, from, to } => {
+                if let Some(obj) = state.get_object_mut(*id) {
+                    obj.rotation = if forward { *to } else { *from };
+                }
+            }
+            Command::Scale { id, from, to } => {
+                if let Some(obj) = state.get_object_mut(*id) {
+                    obj.scale = if forward { *to } else { *from };
+                }
+            }
+            Command::Recolor { id, from, to } => {
+                let (color, accent_color) = if forward { *to } else { *from };
+                if let Some(obj) = state.get_object_mut(*id) {
+                    obj.color = color;
+                    obj.accent_color = accent_color;
+                }
+            }
+            Command::Replace { before, after } => {
+                state.replace_objects(if forward { after.clone() } else { before.clone() });
+            }
+            Command::Batch(commands) => {
+                if forward {
+                    commands.iter().for_each(|command| command.apply(state, true));
+                } else {
+                    commands.iter().rev().for_each(|command| command.apply(state, false));
+                }
+            }
+        }
+        state.mark_dirty();
+    }
+
+    /// Merge a following command into this one if both are part of the same
+    /// continuous edit
+    fn coalesce(&mut self, next: &Command) -> bool {
+        match (self, next) {
+            (Command::Rotate { id, to, .. }, Command::Rotate { id: next_id, to: next_to, .. })
+                if id == next_id =>
+            {
+                *to = *next_to;
+                true
+            }
+            (Command::Scale { id, to, .. }, Command::Scale { id: next_id, to: next_to, .. })
+                if id == next_id =>
+            {
+                *to = *next_to;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether applying the command changes nothing, e.g. a rotation that
+    /// was turned back to where it started
+    fn is_noop(&self) -> bool {
+        match self {
+            // Scrolling back and forth does not land on exactly the same value
+            Command::Rotate { from, to, .. } => from.angle_between(*to) < 1e-4,
+            Command::Scale { from, to, .. } => (from - to).abs() < 1e-4,
+            _ => false,
+        }
+    }
+}
+
+/// Bounded undo/redo stacks
+pub struct History {
+    undo: VecDeque<Command>,
+    redo: Vec<Command>,
+    /// When the newest undo step was last recorded or extended
+    last_recorded: Option<Instant>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            last_recorded: None,
+        }
+    }
+
+    /// Record a command that has already been applied to the state
+    pub fn record(&mut self, command: Command) {
+        self.redo.clear();
+
+        let now = Instant::now();
+        let recent = self
+            .last_recorded
+            .is_some_and(|at| now.duration_since(at) < COALESCE_WINDOW);
+        self.last_recorded = Some(now);
+
+        if recent {
+            if let Some(last) = self.undo.back_mut() {
+                if last.coalesce(&command) {
+                    if last.is_noop() {
+                        // Start afresh rather than merging into an older step
+                        self.undo.pop_back();
+                        self.last_recorded = None;
+                    }
+                    return;
+                }
+            }
+        }
+
+        self.undo.push_back(command);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Undo the newest step, returning whether anything changed
+    pub fn undo(&mut self, state: &mut AppState) -> bool {
+        let Some(command) = self.undo.pop_back() else {
+            return false;
+        };
+        command.apply(state, false);
+        self.redo.push(command);
+        self.last_recorded = None;
+        true
+    }
+
+    /// Redo the most recently undone step, returning whether anything changed
+    pub fn redo(&mut self, state: &mut AppState) -> bool {
+        let Some(command) = self.redo.pop() else {
+            return false;
+        };
+        command.apply(state, true);
+        self.undo.push_back(command);
+        self.last_recorded = None;
+        true
+    }
+
+    /// Forget all steps, e.g. after switching to another desk
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_recorded = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desk_object::ObjectType;
+
+    fn state_with(ids: &[u64]) -> AppState {
+        let mut state = AppState::new();
+        for &id in ids {
+            state.add_object(DeskObject::new(id, ObjectType::Coffee, Vec3::ZERO));
+        }
+        state
+    }
+
+    fn ids(state: &AppState) -> Vec<u64> {
+        state.objects.iter().map(|o| o.id).collect()
+    }
+
+    /// Pretend the newest step was recorded longer ago than the coalescing
+    /// window
+    fn expire_window(history: &mut History) {
+        history.last_recorded = Instant::now().checked_sub(COALESCE_WINDOW);
+    }
+
+    fn rotate(id: u64, from: f32, to: f32) -> Command {
+        Command::Rotate {
+            id,
+            from: Quat::from_rotation_y(from),
+            to: Quat::from_rotation_y(to),
+        }
+    }
+
+    #[test]
+    fn rotations_within_window_are_one_step() {
+        let mut state = state_with(&[1]);
+        let mut history = History::new();
+        history.record(rotate(1, 0.0, 0.5));
+        history.record(rotate(1, 0.5, 1.0));
+        state.get_object_mut(1).unwrap().rotation = Quat::from_rotation_y(1.0);
+
+        assert!(history.undo(&mut state));
+        assert_eq!(state.get_object(1).unwrap().rotation, Quat::from_rotation_y(0.0));
+        assert!(!history.undo(&mut state));
+    }
+
+    #[test]
+    fn rotations_after_window_are_separate_steps() {
+        let mut state = state_with(&[1]);
+        let mut history = History::new();
+        history.record(rotate(1, 0.0, 0.5));
+        expire_window(&mut history);
+        history.record(rotate(1, 0.5, 1.0));
+
+        assert!(history.undo(&mut state));
+        assert_eq!(state.get_object(1).unwrap().rotation, Quat::from_rotation_y(0.5));
+        assert!(history.undo(&mut state));
+        assert_eq!(state.get_object(1).unwrap().rotation, Quat::from_rotation_y(0.0));
+    }
+
+    #[test]
+    fn edits_of_other_objects_or_kinds_are_not_coalesced() {
+        let mut state = state_with(&[1, 2]);
+        let mut history = History::new();
+        history.record(rotate(1, 0.0, 0.5));
+        history.record(rotate(2, 0.0, 0.5));
+        history.record(Command::Scale { id: 2, from: 1.0, to: 1.5 });
+
+        assert!(history.undo(&mut state));
+        assert!(history.undo(&mut state));
+        assert!(history.undo(&mut state));
+        assert!(!history.undo(&mut state));
+    }
+
+    #[test]
+    fn coalesced_scale_back_to_start_is_dropped() {
+        let mut state = state_with(&[1]);
+        let mut history = History::new();
+        history.record(Command::Scale { id: 1, from: 1.0, to: 1.2 });
+        history.record(Command::Scale { id: 1, from: 1.2, to: 1.0 });
+
+        assert!(!history.undo(&mut state));
+    }
+
+    #[test]
+    fn no_op_step_does_not_merge_into_older_step() {
+        let mut state = state_with(&[1]);
+        let mut history = History::new();
+        history.record(rotate(1, 0.0, 0.5));
+        expire_window(&mut history);
+        history.record(rotate(1, 0.5, 1.0));
+        history.record(rotate(1, 1.0, 0.5));
+        history.record(rotate(1, 0.5, 0.8));
+
+        assert!(history.undo(&mut state));
+        assert_eq!(state.get_object(1).unwrap().rotation, Quat::from_rotation_y(0.5));
+        assert!(history.undo(&mut state));
+        assert_eq!(state.get_object(1).unwrap().rotation, Quat::from_rotation_y(0.0));
+    }
+
+    #[test]
+    fn oldest_steps_are_trimmed() {
+        let mut state = state_with(&[1]);
+        let mut history = History::new();
+        for i in 0..HISTORY_LIMIT + 10 {
+            let x = i as f32;
+            history.record(Command::Move {
+                id: 1,
+                from: Vec3::new(x, 0.0, 0.0),
+                to: Vec3::new(x + 1.0, 0.0, 0.0),
+            });
+        }
+
+        let mut steps = 0;
+        while history.undo(&mut state) {
+            steps += 1;
+        }
+        assert_eq!(steps, HISTORY_LIMIT);
+        assert_eq!(state.get_object(1).unwrap().position, Vec3::new(10.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn batch_undoes_in_reverse_order() {
+        let mut state = state_with(&[1]);
+        let mut history = History::new();
+        let (a, b, c) = (Vec3::ZERO, Vec3::X, Vec3::Z);
+        history.record(Command::Batch(vec![
+            Command::Move { id: 1, from: a, to: b },
+            Command::Move { id: 1, from: b, to: c },
+        ]));
+
+        assert!(history.undo(&mut state));
+        assert_eq!(state.get_object(1).unwrap().position, a);
+        assert!(history.redo(&mut state));
+        assert_eq!(state.get_object(1).unwrap().position, c);
+    }
+
+    #[test]
+    fn undo_remove_restores_original_index() {
+        let mut state = state_with(&[1, 2, 3]);
+        let mut history = History::new();
+        let object = state.remove_object(2).unwrap();
+        history.record(Command::Remove { object, index: 1 });
+
+        assert!(history.undo(&mut state));
+        assert_eq!(ids(&state), vec![1, 2, 3]);
+        assert!(history.redo(&mut state));
+        assert_eq!(ids(&state), vec![1, 3]);
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut state = state_with(&[1]);
+        let mut history = History::new();
+        history.record(Command::Move { id: 1, from: Vec3::ZERO, to: Vec3::X });
+        assert!(history.undo(&mut state));
+        history.record(Command::Move { id: 1, from: Vec3::ZERO, to: Vec3::Z });
+
+        assert!(!history.redo(&mut state));
+    }
+}
@@ -0,0 +1,219 @@
+//! Named desk layouts module
+//!
+//! Keeps several desk states ("Deep work", "Meetings", ...) side by side in the
+//! data directory and remembers which one was used last.
+
+use crate::state::{file_slug, write_atomic, AppState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Name of the layout created from the original single state file
+pub const DEFAULT_LAYOUT_NAME: &str = "Default";
+
+/// State file of the default layout (the pre-layouts file name)
+const DEFAULT_LAYOUT_FILE: &str = "desk-state.json";
+
+/// File listing all layouts
+const INDEX_FILE: &str = "layouts.json";
+
+/// A single named layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutEntry {
+    /// Display name shown in the picker
+    pub name: String,
+    /// State file name, relative to the data directory
+    pub file: String,
+}
+
+/// Persisted list of layouts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LayoutIndex {
+    /// Name of the last used layout
+    active: String,
+    /// All known layouts
+    layouts: Vec<LayoutEntry>,
+}
+
+impl Default for LayoutIndex {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_LAYOUT_NAME.to_string(),
+            layouts: vec![LayoutEntry {
+                name: DEFAULT_LAYOUT_NAME.to_string(),
+                file: DEFAULT_LAYOUT_FILE.to_string(),
+            }],
+        }
+    }
+}
+
+/// Manages the set of named layouts and the active one
+pub struct LayoutManager {
+    /// Directory holding the index and all layout state files
+    dir: PathBuf,
+    index: LayoutIndex,
+}
+
+impl LayoutManager {
+    /// Load the layout index from the given directory, creating a default
+    /// index that adopts the existing `desk-state.json` if none exists
+    pub fn load(dir: PathBuf) -> Self {
+        let index_path = dir.join(INDEX_FILE);
+        let mut index = match fs::read_to_string(&index_path) {
+            Ok(content) => match serde_json::from_str::<LayoutIndex>(&content) {
+                Ok(index) => index,
+                Err(e) => {
+                    log::warn!("Layout index is corrupted: {}. Starting a new one.", e);
+                    LayoutIndex::default()
+                }
+            },
+            Err(_) => LayoutIndex::default(),
+        };
+
+        if index.layouts.is_empty() {
+            index = LayoutIndex::default();
+        }
+        if !index.layouts.iter().any(|l| l.name == index.active) {
+            index.active = index.layouts[0].name.clone();
+        }
+
+        Self { dir, index }
+    }
+
+    /// Write the layout index to disk
+    fn save_index(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.index).map_err(|e| e.to_string())?;
+        write_atomic(&self.dir.join(INDEX_FILE), content.as_bytes())
+            .map_err(|e| format!("could not write layout index: {}", e))
+    }
+
+    /// Name of the active layout
+    pub fn active_name(&self) -> &str {
+        &self.index.active
+    }
+
+    /// Names of all layouts in display order
+    pub fn names(&self) -> Vec<String> {
+        self.index.layouts.iter().map(|l| l.name.clone()).collect()
+    }
+
+    /// Full path of a layout's state file
+    pub fn path_of(&self, name: &str) -> Option<PathBuf> {
+        self.index
+            .layouts
+            .iter()
+            .find(|l| l.name == name)
+            .map(|l| self.dir.join(&l.file))
+    }
+
+    /// Full path of the active layout's state file
+    pub fn active_path(&self) -> PathBuf {
+        self.path_of(&self.index.active)
+            .unwrap_or_else(|| self.dir.join(DEFAULT_LAYOUT_FILE))
+    }
+
+    /// Make a layout the active one and remember it for the next start
+    pub fn set_active(&mut self, name: &str) -> Result<(), String> {
+        if self.path_of(name).is_none() {
+            return Err(format!("layout \"{}\" does not exist", name));
+        }
+        self.index.active = name.to_string();
+        self.save_index()
+    }
+
+    /// Register a new layout and return the path of its state file
+    pub fn create(&mut self, name: &str) -> Result<PathBuf, String> {
+        let entry = self.prepare(name)?;
+        self.register(entry)
+    }
+
+    /// Pick the name and state file a new layout would get, without
+    /// registering it yet
+    pub fn prepare(&self, name: &str) -> Result<LayoutEntry, String> {
+        let name = self.validate_new_name(name)?;
+        let file = self.unique_file_name(&name);
+        Ok(LayoutEntry { name, file })
+    }
+
+    /// Full path of a prepared layout's state file
+    pub fn path_of_entry(&self, entry: &LayoutEntry) -> PathBuf {
+        self.dir.join(&entry.file)
+    }
+
+    /// Register a layout from `prepare` and return the path of its state file
+    pub fn register(&mut self, entry: LayoutEntry) -> Result<PathBuf, String> {
+        let name = self.validate_new_name(&entry.name)?;
+        let path = self.dir.join(&entry.file);
+        self.index.layouts.push(LayoutEntry { name, file: entry.file });
+        self.save_index()?;
+        Ok(path)
+    }
+
+    /// Rename a layout; its state file keeps its original name
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        let new_name = self.validate_new_name(new_name)?;
+        let entry = self
+            .index
+            .layouts
+            .iter_mut()
+            .find(|l| l.name == old_name)
+            .ok_or_else(|| format!("layout \"{}\" does not exist", old_name))?;
+        entry.name = new_name.clone();
+        if self.index.active == old_name {
+            self.index.active = new_name;
+        }
+        self.save_index()
+    }
+
+    /// Delete a layout and its state files, returning the name of the layout
+    /// that becomes active if the deleted one was active
+    pub fn delete(&mut self, name: &str) -> Result<String, String> {
+        if self.index.layouts.len() <= 1 {
+            return Err("cannot delete the only layout".to_string());
+        }
+        let pos = self
+            .index
+            .layouts
+            .iter()
+            .position(|l| l.name == name)
+            .ok_or_else(|| format!("layout \"{}\" does not exist", name))?;
+
+        let entry = self.index.layouts.remove(pos);
+        if let Err(e) = AppState::remove_files(&self.dir.join(&entry.file)) {
+            log::warn!("Could not remove files of layout \"{}\": {}", name, e);
+        }
+        if self.index.active == name {
+            self.index.active = self.index.layouts[pos.saturating_sub(1)].name.clone();
+        }
+        self.save_index()?;
+        Ok(self.index.active.clone())
+    }
+
+    /// Check that a proposed layout name is usable and not taken
+    fn validate_new_name(&self, name: &str) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("layout name cannot be empty".to_string());
+        }
+        if self.index.layouts.iter().any(|l| l.name == name) {
+            return Err(format!("a layout named \"{}\" already exists", name));
+        }
+        Ok(name.to_string())
+    }
+
+    /// Derive a state file name from a layout name that no other layout uses
+    fn unique_file_name(&self, name: &str) -> String {
+        let slug = file_slug(name, "layout");
+
+        let taken = |file: &str| {
+            self.index.layouts.iter().any(|l| l.file == file) || self.dir.join(file).exists()
+        };
+        let mut file = format!("layout-{}.json", slug);
+        let mut suffix = 2;
+        while taken(&file) {
+            file = format!("layout-{}-{}.json", slug, suffix);
+            suffix += 1;
+        }
+        file
+    }
+}
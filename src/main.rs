@@ -3,36 +3,56 @@
 //! A Rust implementation of the Focus Desktop Simulator with an isometric 3D desk
 //! and interactive objects. Uses wgpu for GPU rendering and egui for UI.
 
+mod bindings;
+mod bundle;
 mod camera;
+mod cli;
 mod config;
+mod desk;
 mod desk_object;
+mod history;
+mod layouts;
 mod mesh;
 mod physics;
+mod pixelation;
 mod state;
+mod theme;
 mod ui;
 
-use camera::Camera;
-use config::{hex_to_rgb, hex_to_rgba, CONFIG};
-use desk_object::{DeskObject, ObjectType};
+use camera::{Camera, CameraMode};
+use clap::Parser;
+use cli::Cli;
+use config::{hex_to_rgba, Config, DeskConfig};
+use bindings::{Action, KeyMap};
+use desk::Desk;
+use desk_object::{DeskObject, ObjectType, MAX_SCALE, MIN_SCALE};
+use history::{Command, History};
+use layouts::LayoutManager;
 use mesh::{generate_object_mesh, MeshData, Vertex};
 use physics::PhysicsEngine;
-use state::AppState;
-use ui::{render_left_sidebar, render_right_sidebar, UiAction, UiState};
+use pixelation::PixelationPass;
+use state::{AppState, AutoSaver, CameraPose, ViewBookmark, ViewState, WindowGeometry};
+use theme::{Theme, ThemeLibrary};
+use ui::{
+    render_bindings_window, render_external_change_prompt, render_left_sidebar, render_right_sidebar, render_settings_window,
+    ExternalChangeChoice, UiAction, UiState,
+};
 
 use egui_wgpu::ScreenDescriptor;
 use glam::{Mat4, Quat, Vec3};
 use log::info;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use wgpu::util::DeviceExt;
 use winit::{
     application::ApplicationHandler,
-    dpi::PhysicalSize,
-    event::{ElementState, MouseButton, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, MouseButton, StartCause, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowAttributes, WindowId},
+    keyboard::{ModifiersState, PhysicalKey},
+    window::{Fullscreen, Window, WindowAttributes, WindowId},
 };
 
 /// Camera uniform buffer data
@@ -62,6 +82,31 @@ impl CameraUniform {
     }
 }
 
+/// Lighting and fog uniform buffer data
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightingUniform {
+    light_direction: [f32; 4],
+    ambient_color: [f32; 4],
+    directional_color: [f32; 4],
+    fog_color: [f32; 4],
+    /// Fog start distance, fade range and maximum blend
+    fog_params: [f32; 4],
+}
+
+impl LightingUniform {
+    fn from_config(config: &Config) -> Self {
+        let lighting = &config.lighting;
+        Self {
+            light_direction: lighting.direction.normalize().extend(0.0).to_array(),
+            ambient_color: hex_to_rgba(config.colors.ambient),
+            directional_color: hex_to_rgba(config.colors.directional),
+            fog_color: hex_to_rgba(config.colors.background),
+            fog_params: [lighting.fog_start, lighting.fog_range, lighting.fog_max, 0.0],
+        }
+    }
+}
+
 /// Model uniform buffer data for per-object transforms
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
@@ -76,8 +121,7 @@ impl ModelUniform {
         }
     }
 
-    fn from_transform(position: Vec3, rotation: Quat, scale: f32) -> Self {
-        let model = Mat4::from_scale_rotation_translation(Vec3::splat(scale), rotation, position);
+    fn from_matrix(model: Mat4) -> Self {
         Self {
             model: model.to_cols_array_2d(),
         }
@@ -123,20 +167,61 @@ struct App {
     size: PhysicalSize<u32>,
     render_pipeline: wgpu::RenderPipeline,
     camera_buffer: wgpu::Buffer,
+    lighting_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
     model_bind_group_layout: wgpu::BindGroupLayout,
-    depth_texture: wgpu::TextureView,
+    /// Offscreen scene targets and the pass pixelating them to the window
+    pixelation: PixelationPass,
     desk_mesh: GpuMesh,
     floor_mesh: GpuMesh,
     object_meshes: HashMap<u64, (GpuMesh, wgpu::Buffer, wgpu::BindGroup)>,
     camera: Camera,
+    /// User configuration, editable at runtime from the settings window
+    settings: Config,
+    /// When the settings were last changed and not yet saved
+    settings_changed_at: Option<Instant>,
+    themes: ThemeLibrary,
     state: AppState,
+    autosaver: AutoSaver,
+    layouts: LayoutManager,
+    history: History,
     physics: PhysicsEngine,
     mouse_position: (f32, f32),
-    left_mouse_down: bool,
+    /// Mouse button held for the current drag
+    drag_button: Option<MouseButton>,
     dragging_object_id: Option<u64>,
+    /// Resting position of the dragged object when the drag started
+    drag_start_position: Option<Vec3>,
+    /// Simulation time not yet run as a physics step, in seconds
+    physics_accumulator: f32,
+    /// Objects as they were before the last physics step
+    previous_objects: Vec<DeskObject>,
+    /// Objects that moved in the last physics step, drawn interpolated
+    interpolated_ids: Vec<u64>,
+    /// Whether the last frame had anything moving by itself
+    animating: bool,
+    /// When egui last asked to be drawn again
+    egui_repaint_at: Option<Instant>,
+    /// Recent cursor positions on the drag plane, for the throw velocity
+    drag_samples: VecDeque<(Instant, Vec3)>,
+    /// Resting poses from before the current throw, recorded as one undo
+    /// step once every object has come to rest
+    motion_start: Option<Vec<(u64, Vec3, Quat)>>,
+    /// Mouse button held to look around, orbit or pan the camera
+    camera_drag_button: Option<MouseButton>,
+    /// Context-click button pressed but not yet released, with the cursor
+    /// position at the press; moving far enough turns it into a camera drag
+    context_press: Option<(MouseButton, (f32, f32))>,
+    /// Time and object of the last drag click, for detecting double-clicks
+    last_click: Option<(Instant, u64)>,
+    /// Saved camera views of the current desk
+    view_bookmarks: Vec<ViewBookmark>,
+    /// Bookmark the camera last moved to
+    current_bookmark: Option<usize>,
     last_frame_time: Instant,
-    shift_pressed: bool,
+    modifiers: ModifiersState,
+    /// Active key and mouse bindings
+    keymap: KeyMap,
     current_object_type_index: usize,
     // Egui integration
     egui_ctx: egui::Context,
@@ -146,7 +231,12 @@ struct App {
 }
 
 impl App {
-    async fn new(window: Arc<Window>) -> Result<Self, Box<dyn std::error::Error>> {
+    async fn new(
+        window: Arc<Window>,
+        settings: Config,
+        layouts: LayoutManager,
+        app_state: AppState,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let size = window.inner_size();
         let aspect = size.width as f32 / size.height as f32;
 
@@ -217,28 +307,54 @@ impl App {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        // Create camera bind group layout
+        // Create lighting uniform buffer
+        let lighting_uniform = LightingUniform::from_config(&settings);
+        let lighting_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Lighting Buffer"),
+            contents: bytemuck::cast_slice(&[lighting_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Create camera bind group layout (camera and scene lighting)
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
                 label: Some("camera_bind_group_layout"),
             });
 
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: lighting_buffer.as_entire_binding(),
+                },
+            ],
             label: Some("camera_bind_group"),
         });
 
@@ -278,11 +394,18 @@ impl App {
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: pixelation::NORMAL_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             }),
             primitive: wgpu::PrimitiveState {
@@ -295,7 +418,7 @@ impl App {
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
+                format: pixelation::DEPTH_FORMAT,
                 depth_write_enabled: true,
                 depth_compare: wgpu::CompareFunction::Less,
                 stencil: wgpu::StencilState::default(),
@@ -310,30 +433,32 @@ impl App {
             cache: None,
         });
 
-        // Create depth texture
-        let depth_texture = Self::create_depth_texture(&device, &config);
+        // Create the offscreen scene targets (including the depth texture)
+        let pixelation = PixelationPass::new(&device, config.format, (config.width, config.height), &settings.pixelation);
 
         // Create static meshes
-        let desk_mesh = Self::create_desk_mesh(&device);
-        let floor_mesh = Self::create_floor_mesh(&device);
+        let desk_mesh = Self::create_desk_mesh(&device, &settings.desk);
+        let floor_mesh = Self::create_floor_mesh(&device, &settings.desk, settings.colors.ground);
 
         // Create camera
-        let camera = Camera::new(aspect);
+        let mut camera = Camera::new(aspect, &settings.camera);
 
-        // Load state
-        let app_state = AppState::load();
-        let mut physics = PhysicsEngine::new();
-        physics.collision_radius_multiplier = app_state.collision_radius_multiplier;
+        let physics = PhysicsEngine::new(&settings);
+        camera.frame_desk(&physics.desk);
 
         // Initialize egui
         let egui_ctx = egui::Context::default();
 
-        // Set up dark theme for egui
-        let mut style = egui::Style::default();
-        style.visuals = egui::Visuals::dark();
-        style.visuals.window_fill = egui::Color32::from_rgba_unmultiplied(26, 26, 46, 242);
-        style.visuals.panel_fill = egui::Color32::from_rgba_unmultiplied(26, 26, 46, 242);
-        egui_ctx.set_style(style);
+        // Set up egui colors from the selected theme
+        let themes = ThemeLibrary::load();
+        if themes.get(&settings.theme).is_none() {
+            log::warn!("Unknown theme \"{}\", using default UI colors", settings.theme);
+        }
+        let theme = themes.get_or_default(&settings.theme).clone();
+        egui_ctx.set_style(egui::Style {
+            visuals: theme.visuals(),
+            ..Default::default()
+        });
 
         let egui_state = egui_winit::State::new(
             egui_ctx.clone(),
@@ -346,7 +471,19 @@ impl App {
 
         let egui_renderer = egui_wgpu::Renderer::new(&device, config.format, None, 1, false);
 
-        let ui_state = UiState::new();
+        let mut ui_state = UiState::new();
+        ui_state.set_theme(&theme);
+        let keymap = KeyMap::from_config(&settings.bindings);
+        for conflict in keymap.conflicts() {
+            log::warn!("Bindings: {}", conflict);
+        }
+        ui_state.control_hints = keymap.describe();
+        ui_state.theme_names = themes.names();
+        ui_state.bundle_path_input = dirs::home_dir()
+            .unwrap_or_default()
+            .join(format!("desk.{}", bundle::BUNDLE_EXTENSION))
+            .to_string_lossy()
+            .into_owned();
 
         let mut app = Self {
             window,
@@ -357,20 +494,41 @@ impl App {
             size,
             render_pipeline,
             camera_buffer,
+            lighting_buffer,
             camera_bind_group,
             model_bind_group_layout,
-            depth_texture,
+            pixelation,
             desk_mesh,
             floor_mesh,
             object_meshes: HashMap::new(),
             camera,
+            settings,
+            settings_changed_at: None,
+            themes,
             state: app_state,
+            autosaver: AutoSaver::new(),
+            layouts,
+            history: History::new(),
             physics,
             mouse_position: (0.0, 0.0),
-            left_mouse_down: false,
+            drag_button: None,
             dragging_object_id: None,
+            drag_start_position: None,
+            physics_accumulator: 0.0,
+            previous_objects: Vec::new(),
+            interpolated_ids: Vec::new(),
+            animating: false,
+            egui_repaint_at: None,
+            drag_samples: VecDeque::new(),
+            motion_start: None,
+            camera_drag_button: None,
+            context_press: None,
+            last_click: None,
+            view_bookmarks: Vec::new(),
+            current_bookmark: None,
             last_frame_time: Instant::now(),
-            shift_pressed: false,
+            modifiers: ModifiersState::empty(),
+            keymap,
             current_object_type_index: 0,
             egui_ctx,
             egui_state,
@@ -378,8 +536,12 @@ impl App {
             ui_state,
         };
 
-        // Create meshes for existing objects
+        // Restore physics settings, resting heights and the saved view, then
+        // create meshes
+        app.settle_loaded_state();
+        app.restore_view_state();
         app.rebuild_object_meshes();
+        app.autosaver.watch(&app.state);
 
         Ok(app)
     }
@@ -393,9 +555,7 @@ impl App {
                 obj.object_type,
                 obj.color,
                 obj.accent_color,
-                obj.position,
-                obj.rotation,
-                obj.scale,
+                obj.model_matrix(),
             );
         }
     }
@@ -406,9 +566,7 @@ impl App {
             obj.object_type,
             obj.color,
             obj.accent_color,
-            obj.position,
-            obj.rotation,
-            obj.scale,
+            obj.model_matrix(),
         );
     }
 
@@ -418,14 +576,12 @@ impl App {
         object_type: ObjectType,
         color: u32,
         accent_color: u32,
-        position: Vec3,
-        rotation: Quat,
-        scale: f32,
+        model: Mat4,
     ) {
         let mesh_data = generate_object_mesh(object_type, color, accent_color);
         let gpu_mesh = GpuMesh::from_mesh_data(&self.device, &mesh_data);
 
-        let model_uniform = ModelUniform::from_transform(position, rotation, scale);
+        let model_uniform = ModelUniform::from_matrix(model);
         let model_buffer = self
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -449,12 +605,28 @@ impl App {
 
     fn update_object_transform(&mut self, id: u64) {
         if let Some(obj) = self.state.get_object(id) {
-            if let Some((_, buffer, _)) = self.object_meshes.get(&id) {
-                let model_uniform =
-                    ModelUniform::from_transform(obj.position, obj.rotation, obj.scale);
-                self.queue
-                    .write_buffer(buffer, 0, bytemuck::cast_slice(&[model_uniform]));
-            }
+            self.write_object_model(id, obj.model_matrix());
+        }
+    }
+
+    /// Draw a moving object between its poses at the last two physics steps;
+    /// held objects follow the cursor directly
+    fn update_interpolated_transform(&mut self, id: u64, alpha: f32) {
+        let Some(obj) = self.state.get_object(id) else {
+            return;
+        };
+        let model = match self.previous_objects.iter().find(|o| o.id == id) {
+            Some(previous) if !obj.is_dragging => obj.interpolated_from(previous, alpha).model_matrix(),
+            _ => obj.model_matrix(),
+        };
+        self.write_object_model(id, model);
+    }
+
+    fn write_object_model(&self, id: u64, model: Mat4) {
+        if let Some((_, buffer, _)) = self.object_meshes.get(&id) {
+            let model_uniform = ModelUniform::from_matrix(model);
+            self.queue
+                .write_buffer(buffer, 0, bytemuck::cast_slice(&[model_uniform]));
         }
     }
 
@@ -464,7 +636,8 @@ impl App {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
-            self.depth_texture = Self::create_depth_texture(&self.device, &self.config);
+            self.pixelation
+                .resize(&self.device, (new_size.width, new_size.height), &self.settings.pixelation);
             self.camera
                 .set_aspect(new_size.width as f32 / new_size.height as f32);
         }
@@ -472,25 +645,49 @@ impl App {
 
     fn update(&mut self) {
         let now = Instant::now();
-        let _dt = (now - self.last_frame_time).as_secs_f32();
+        let elapsed = (now - self.last_frame_time).as_secs_f32();
         self.last_frame_time = now;
-
-        // Update physics for dropping objects
-        let objects_clone: Vec<DeskObject> = self.state.objects.clone();
-        let mut updated_ids: Vec<u64> = Vec::new();
-        for obj in &mut self.state.objects {
-            if !obj.is_dragging {
-                if self
-                    .physics
-                    .update_dropping(obj, &objects_clone, CONFIG.physics.drop_speed)
-                {
-                    updated_ids.push(obj.id);
+        // Coming out of an idle wait, motion starts from a single step
+        let dt = if self.animating { elapsed } else { elapsed.min(PHYSICS_STEP) }.min(MAX_FRAME_TIME);
+
+        self.camera.update(dt);
+
+        // Run the simulation in fixed steps, drawing moving objects between
+        // the poses of the last two
+        self.physics_accumulator += dt;
+        while self.physics_accumulator >= PHYSICS_STEP {
+            self.previous_objects = self.state.objects.clone();
+            let moved = self.step_physics();
+            // Objects that just came to rest are drawn where they stopped
+            for id in std::mem::replace(&mut self.interpolated_ids, moved) {
+                if !self.interpolated_ids.contains(&id) {
+                    self.update_object_transform(id);
                 }
             }
+            self.physics_accumulator -= PHYSICS_STEP;
+        }
+        let alpha = self.physics_accumulator / PHYSICS_STEP;
+        for id in self.interpolated_ids.clone() {
+            self.update_interpolated_transform(id, alpha);
         }
 
-        for id in updated_ids {
-            self.update_object_transform(id);
+        let settled = self.state.objects.iter().all(|o| {
+            o.is_dragging || (o.physics_state.is_resting() && o.position.y == o.target_y)
+        });
+        if settled {
+            self.finish_motion();
+        }
+
+        if self.autosaver.check_external_change(&self.state) {
+            self.on_external_change();
+        }
+        self.autosaver.poll(&mut self.state);
+
+        if self
+            .settings_changed_at
+            .is_some_and(|at| at.elapsed() >= SETTINGS_SAVE_DELAY)
+        {
+            self.save_settings();
         }
 
         // Update camera uniform
@@ -498,6 +695,57 @@ impl App {
         camera_uniform.update(&self.camera);
         self.queue
             .write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
+
+        self.animating = self.is_animating();
+    }
+
+    /// Advance the desk simulation by one fixed step, returning the ids of
+    /// objects that moved
+    fn step_physics(&mut self) -> Vec<u64> {
+        // Slide, spin and collide released objects
+        let mut moved = self.physics.step(&mut self.state.objects, PHYSICS_STEP);
+        if !moved.is_empty() {
+            self.state.mark_dirty();
+        }
+
+        // Lift held objects and drop released ones
+        let physics_config = &self.settings.physics;
+        for obj in &mut self.state.objects {
+            let animating = if obj.is_dragging {
+                self.physics
+                    .update_lifting(obj, physics_config.lift_height, physics_config.lift_speed, PHYSICS_STEP)
+            } else {
+                self.physics.update_dropping(obj, physics_config.drop_speed, PHYSICS_STEP)
+            };
+            if animating && !moved.contains(&obj.id) {
+                moved.push(obj.id);
+            }
+        }
+        moved
+    }
+
+    /// Whether anything on screen is still moving by itself, so frames must
+    /// keep coming without input
+    fn is_animating(&self) -> bool {
+        self.camera.is_animating()
+            || !self.interpolated_ids.is_empty()
+            || self
+                .state
+                .objects
+                .iter()
+                .any(|o| !o.physics_state.is_resting() || o.position.y != o.target_y)
+            || self.egui_repaint_at.is_some_and(|at| at <= Instant::now())
+    }
+
+    /// When the app next needs a frame without input: right away while
+    /// animating, otherwise when egui asks for one or after a short idle
+    /// interval for autosave and file watching
+    fn next_frame_at(&self) -> Option<Instant> {
+        if self.animating || self.is_animating() {
+            return None;
+        }
+        let idle = Instant::now() + IDLE_FRAME_INTERVAL;
+        Some(self.egui_repaint_at.map_or(idle, |at| at.min(idle)))
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -530,25 +778,37 @@ impl App {
             label: Some("identity_model_bind_group"),
         });
 
+        // The scene is drawn offscreen at the pixelated resolution, then
+        // scaled up into the window below
         {
-            let bg_color = hex_to_rgba(CONFIG.colors.background);
+            let bg_color = hex_to_rgba(self.settings.colors.background);
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: bg_color[0] as f64,
-                            g: bg_color[1] as f64,
-                            b: bg_color[2] as f64,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: self.pixelation.color_view(),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color {
+                                r: bg_color[0] as f64,
+                                g: bg_color[1] as f64,
+                                b: bg_color[2] as f64,
+                                a: 1.0,
+                            }),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: self.pixelation.normal_view(),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                ],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_texture,
+                    view: self.pixelation.depth_view(),
                     depth_ops: Some(wgpu::Operations {
                         load: wgpu::LoadOp::Clear(1.0),
                         store: wgpu::StoreOp::Store,
@@ -591,8 +851,16 @@ impl App {
             }
         }
 
+        self.pixelation.update(&self.queue, &self.settings.pixelation, &self.camera);
+        self.pixelation.draw(&mut encoder, &view);
+
         // Render egui UI
         // Note: We need to prepare UI data before running egui to avoid borrow issues
+        self.ui_state.has_unsaved_changes = self.state.is_dirty();
+        self.ui_state.layout_names = self.layouts.names();
+        self.ui_state.active_layout = self.layouts.active_name().to_string();
+        self.ui_state.camera_mode = self.camera.mode;
+        self.ui_state.bookmark_names = self.view_bookmarks.iter().map(|b| b.name.clone()).collect();
         let object_name = if let Some(id) = self.ui_state.selected_object_id {
             self.state.get_object(id).map(|obj| obj.object_type.display_name().to_string())
         } else {
@@ -611,6 +879,13 @@ impl App {
             // Render right sidebar (customization)
             let right_actions = render_right_sidebar(ctx, &mut self.ui_state, object_name.as_deref());
             ui_actions.extend(right_actions);
+
+            // Settings window (edits a copy of the running config)
+            ui_actions.extend(render_settings_window(ctx, &mut self.ui_state, &self.settings));
+            ui_actions.extend(render_bindings_window(ctx, &mut self.ui_state, &self.settings));
+
+            // Ask what to do when the state file changed under unsaved edits
+            ui_actions.extend(render_external_change_prompt(ctx, &self.ui_state));
         });
 
         // Process UI actions after egui rendering
@@ -618,6 +893,12 @@ impl App {
             self.process_ui_action(action);
         }
 
+        // Remember when egui wants to draw again, e.g. for a blinking cursor
+        self.egui_repaint_at = egui_output
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .and_then(|viewport| Instant::now().checked_add(viewport.repaint_delay));
+
         // Handle egui platform output
         self.egui_state.handle_platform_output(&self.window, egui_output.platform_output);
 
@@ -672,37 +953,157 @@ impl App {
                 info!("Added {} from UI", object_type.display_name());
             }
             UiAction::DeleteObject(id) => {
-                self.state.remove_object(id);
-                self.object_meshes.remove(&id);
+                self.delete_object(id);
                 self.ui_state.close_customization();
                 info!("Deleted object {} from UI", id);
             }
             UiAction::ChangeMainColor(id, color) => {
-                if let Some(obj) = self.state.get_object_mut(id) {
-                    obj.color = color;
-                }
-                // Rebuild mesh with new color
-                if let Some(obj) = self.state.get_object(id).cloned() {
-                    self.object_meshes.remove(&id);
-                    self.create_object_mesh(&obj);
+                if let Some(obj) = self.state.get_object(id) {
+                    let accent_color = obj.accent_color;
+                    self.recolor_object(id, color, accent_color);
                 }
             }
             UiAction::ChangeAccentColor(id, color) => {
-                if let Some(obj) = self.state.get_object_mut(id) {
-                    obj.accent_color = color;
-                }
-                // Rebuild mesh with new color
-                if let Some(obj) = self.state.get_object(id).cloned() {
-                    self.object_meshes.remove(&id);
-                    self.create_object_mesh(&obj);
+                if let Some(obj) = self.state.get_object(id) {
+                    let main_color = obj.color;
+                    self.recolor_object(id, main_color, color);
                 }
             }
             UiAction::ClearAll => {
-                self.state.objects.clear();
+                let before = self.state.objects.clone();
+                self.state.clear_objects();
+                self.history.record(Command::Replace {
+                    before,
+                    after: Vec::new(),
+                });
                 self.object_meshes.clear();
                 self.ui_state.close_customization();
                 info!("Cleared all objects from UI");
             }
+            UiAction::SaveNow => match self.save_state() {
+                Ok(()) => info!("Saved state from UI"),
+                Err(e) => log::error!("Failed to save state: {}", e),
+            },
+            UiAction::WriteConfig => {
+                self.ui_state.config_status = Some(match self.settings.write_effective() {
+                    Ok(path) => format!("Config written to {}", path.display()),
+                    Err(e) => format!("Could not write config: {}", e),
+                });
+            }
+            UiAction::UpdateSettings(settings) => self.apply_settings(*settings),
+            UiAction::SwitchTheme(name) => self.switch_theme(&name),
+            UiAction::SaveTheme(name) => {
+                let base = self.themes.get_or_default(&self.settings.theme);
+                let theme = Theme::capture(&name, &self.settings, base);
+                match self.themes.save_user_theme(theme) {
+                    Ok(path) => {
+                        self.ui_state.theme_name_input.clear();
+                        self.ui_state.theme_names = self.themes.names();
+                        self.ui_state.config_status = Some(format!("Theme saved to {}", path.display()));
+                        self.switch_theme(name.trim());
+                    }
+                    Err(e) => self.ui_state.config_status = Some(format!("Could not save theme: {}", e)),
+                }
+            }
+            UiAction::SwitchLayout(name) => self.switch_layout(&name),
+            UiAction::CreateLayout(name) => self.create_layout(&name, false),
+            UiAction::DuplicateLayout(name) => self.create_layout(&name, true),
+            UiAction::RenameLayout(name) => {
+                let active = self.layouts.active_name().to_string();
+                match self.layouts.rename(&active, &name) {
+                    Ok(()) => {
+                        self.ui_state.layout_finished();
+                        info!("Renamed layout \"{}\" to \"{}\"", active, name.trim());
+                    }
+                    Err(e) => self.ui_state.layout_error = Some(e),
+                }
+            }
+            UiAction::DeleteLayout => {
+                // A running autosave must not recreate the deleted files
+                self.autosaver.flush(&mut self.state);
+                let active = self.layouts.active_name().to_string();
+                match self.layouts.delete(&active) {
+                    Ok(next) => {
+                        // The deleted layout's state must not be written back
+                        self.state = AppState::load_from(self.layouts.active_path());
+                        self.on_state_replaced();
+                        self.ui_state.layout_finished();
+                        info!("Deleted layout \"{}\", switched to \"{}\"", active, next);
+                    }
+                    Err(e) => {
+                        self.ui_state.confirm_layout_delete = false;
+                        self.ui_state.layout_error = Some(e);
+                    }
+                }
+            }
+            UiAction::ExportBundle(path) => {
+                self.ui_state.bundle_status =
+                    Some(match bundle::export_bundle(&self.state, &PathBuf::from(path)) {
+                        Ok(()) => format!("Exported {} objects", self.state.objects.len()),
+                        Err(e) => format!("Export failed: {}", e),
+                    });
+            }
+            UiAction::ImportBundle(path, mode) => {
+                self.stop_motion();
+                let before = self.state.objects.clone();
+                match bundle::import_bundle(&mut self.state, &PathBuf::from(path), mode) {
+                    Ok(report) => {
+                        // Objects from another desk may be off this one or
+                        // at their old stack height
+                        self.settle_loaded_state();
+                        self.history.record(Command::Replace {
+                            before,
+                            after: self.state.objects.clone(),
+                        });
+                        let mut status = report.summary();
+                        for line in report.details() {
+                            status.push_str("\n• ");
+                            status.push_str(line);
+                        }
+                        self.ui_state.bundle_status = Some(status);
+                        self.ui_state.close_customization();
+                        self.rebuild_object_meshes();
+                    }
+                    Err(e) => self.ui_state.bundle_status = Some(format!("Import failed: {}", e)),
+                }
+            }
+            UiAction::ResolveExternalChange(choice) => {
+                self.ui_state.external_change_pending = false;
+                match choice {
+                    ExternalChangeChoice::LoadFromDisk => self.reload_from_disk(),
+                    ExternalChangeChoice::KeepMine => {
+                        // Our version overwrites the external one on the next autosave
+                        self.autosaver.watch(&self.state);
+                        self.state.mark_dirty();
+                        info!("Keeping in-app changes over external edit");
+                    }
+                }
+            }
+            UiAction::SetCameraMode(mode) => self.set_camera_mode(mode),
+            UiAction::GoToBookmark(index) => self.go_to_bookmark(index),
+            UiAction::SaveBookmark(name) => {
+                let name = name.trim().to_string();
+                let view = self.camera.view();
+                match self.view_bookmarks.iter().position(|b| b.name == name) {
+                    Some(index) => {
+                        self.view_bookmarks[index].view = view;
+                        self.current_bookmark = Some(index);
+                    }
+                    None => {
+                        self.view_bookmarks.push(ViewBookmark { name, view });
+                        self.current_bookmark = Some(self.view_bookmarks.len() - 1);
+                    }
+                }
+                self.ui_state.bookmark_name_input.clear();
+                self.store_view_state();
+            }
+            UiAction::DeleteBookmark(index) => {
+                if index < self.view_bookmarks.len() {
+                    self.view_bookmarks.remove(index);
+                    self.current_bookmark = None;
+                    self.store_view_state();
+                }
+            }
             UiAction::CloseCustomization => {
                 self.ui_state.close_customization();
             }
@@ -712,45 +1113,88 @@ impl App {
 
     /// Handle a window event, returning whether egui consumed it
     fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        // Track modifiers even when egui has focus so bindings see them
+        if let WindowEvent::ModifiersChanged(modifiers) = event {
+            self.modifiers = modifiers.state();
+        }
+
         // First pass event to egui
         let response = self.egui_state.on_window_event(&self.window, event);
 
         // If egui consumed the event, don't process it further
         if response.consumed {
+            // A release over the UI still ends camera gestures started on the desk
+            if let WindowEvent::MouseInput { button, state: ElementState::Released, .. } = event {
+                if self.camera_drag_button == Some(*button) {
+                    self.camera_drag_button = None;
+                }
+                if self.context_press.is_some_and(|(pressed, _)| pressed == *button) {
+                    self.context_press = None;
+                }
+            }
             return true;
         }
 
         match event {
-            WindowEvent::MouseInput { button, state, .. } => {
-                if *button == MouseButton::Left {
-                    self.left_mouse_down = *state == ElementState::Pressed;
-                    if !self.left_mouse_down {
-                        // End drag
-                        if let Some(id) = self.dragging_object_id.take() {
-                            let objects_clone: Vec<DeskObject> = self.state.objects.clone();
-                            if let Some(obj) = self.state.get_object_mut(id) {
-                                self.physics.end_drag(obj, &objects_clone);
-                                self.update_object_transform(id);
+            WindowEvent::MouseInput { button, state, .. } => match state {
+                ElementState::Pressed => match self.keymap.mouse_action(*button, self.modifiers) {
+                    Some(Action::Drag) => {
+                        let clicked = self.find_object_at_cursor();
+                        let double_click = clicked.is_some_and(|id| {
+                            self.last_click
+                                .is_some_and(|(at, last_id)| last_id == id && at.elapsed() <= DOUBLE_CLICK_TIME)
+                        });
+                        if double_click {
+                            self.last_click = None;
+                            if let Some(id) = clicked {
+                                // Double-clicking a toppled object stands it back up
+                                if self.state.get_object(id).is_some_and(|o| o.physics_state.is_toppled()) {
+                                    self.right_object(id);
+                                } else {
+                                    self.focus_object(id);
+                                }
                             }
+                        } else {
+                            self.last_click = clicked.map(|id| (Instant::now(), id));
+                            self.drag_button = Some(*button);
+                            self.try_pick_object();
                         }
-                    } else {
-                        self.try_pick_object();
                     }
-                } else if *button == MouseButton::Right && *state == ElementState::Pressed {
-                    // Right-click to open customization panel for clicked object
-                    if let Some(id) = self.find_object_at_cursor() {
-                        if let Some(obj) = self.state.get_object(id) {
-                            self.ui_state.open_customization(id, obj.color, obj.accent_color);
-                        }
-                    } else {
-                        // Right-click on empty space toggles the left sidebar
-                        self.ui_state.toggle_left_sidebar();
+                    Some(Action::CameraDrag) => self.camera_drag_button = Some(*button),
+                    // The click acts on release, so dragging can look around instead
+                    Some(Action::ContextClick) => self.context_press = Some((*button, self.mouse_position)),
+                    Some(action) => self.perform_action(action),
+                    None => {}
+                },
+                ElementState::Released => {
+                    if self.drag_button == Some(*button) {
+                        self.drag_button = None;
+                        self.end_drag();
+                    }
+                    if self.camera_drag_button == Some(*button) {
+                        self.camera_drag_button = None;
+                    }
+                    if self.context_press.is_some_and(|(pressed, _)| pressed == *button) {
+                        self.context_press = None;
+                        self.context_click();
                     }
                 }
-            }
+            },
             WindowEvent::CursorMoved { position, .. } => {
+                let (last_x, last_y) = self.mouse_position;
                 self.mouse_position = (position.x as f32, position.y as f32);
-                if self.left_mouse_down && self.dragging_object_id.is_some() {
+                if let Some((button, (start_x, start_y))) = self.context_press {
+                    let (x, y) = self.mouse_position;
+                    if (x - start_x).hypot(y - start_y) > CONTEXT_DRAG_THRESHOLD {
+                        self.context_press = None;
+                        self.camera_drag_button = Some(button);
+                    }
+                }
+                if self.camera_drag_button.is_some() {
+                    let (x, y) = self.mouse_position;
+                    self.camera.drag_view(x - last_x, y - last_y, self.size.height as f32);
+                }
+                if self.drag_button.is_some() && self.dragging_object_id.is_some() {
                     self.update_drag();
                 }
             }
@@ -759,90 +1203,41 @@ impl App {
                     winit::event::MouseScrollDelta::LineDelta(_, y) => *y,
                     winit::event::MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 50.0,
                 };
-                if let Some(id) = self.dragging_object_id {
-                    if self.shift_pressed {
+                // Without a held object the wheel zooms the camera
+                let Some(id) = self.dragging_object_id else {
+                    self.camera.zoom(scroll);
+                    return false;
+                };
+                match self.keymap.scroll_action(self.modifiers) {
+                    Some(Action::Scale) => {
+                        if let Some(obj) = self.state.get_object_mut(id) {
+                            let from = obj.scale;
+                            obj.scale = (obj.scale + scroll * 0.1).clamp(MIN_SCALE, MAX_SCALE);
+                            let to = obj.scale;
+                            self.state.mark_dirty();
+                            self.update_object_transform(id);
+                            self.history.record(Command::Scale { id, from, to });
+                        }
+                    }
+                    Some(Action::Rotate) => {
                         if let Some(obj) = self.state.get_object_mut(id) {
-                            obj.scale = (obj.scale + scroll * 0.1).clamp(0.3, 3.0);
+                            let from = obj.rotation;
+                            obj.rotation = Quat::from_rotation_y(scroll * 0.2) * obj.rotation;
+                            let to = obj.rotation;
+                            self.state.mark_dirty();
                             self.update_object_transform(id);
+                            self.history.record(Command::Rotate { id, from, to });
                         }
-                    } else if let Some(obj) = self.state.get_object_mut(id) {
-                        obj.rotation = Quat::from_rotation_y(scroll * 0.2) * obj.rotation;
-                        self.update_object_transform(id);
                     }
+                    _ => {}
                 }
             }
             WindowEvent::KeyboardInput { event, .. } => {
                 if let PhysicalKey::Code(key) = event.physical_key {
-                    match key {
-                        KeyCode::ShiftLeft | KeyCode::ShiftRight => {
-                            self.shift_pressed = event.state == ElementState::Pressed;
-                        }
-                        KeyCode::KeyA if event.state == ElementState::Pressed => {
-                            // Add object of current type
-                            let object_types = [
-                                ObjectType::Clock,
-                                ObjectType::Lamp,
-                                ObjectType::Plant,
-                                ObjectType::Coffee,
-                                ObjectType::Laptop,
-                                ObjectType::Notebook,
-                                ObjectType::PenHolder,
-                                ObjectType::Books,
-                                ObjectType::PhotoFrame,
-                                ObjectType::Globe,
-                                ObjectType::Trophy,
-                                ObjectType::Hourglass,
-                                ObjectType::Metronome,
-                                ObjectType::Paper,
-                                ObjectType::Magazine,
-                            ];
-                            let obj_type = object_types[self.current_object_type_index];
-                            self.add_object(obj_type);
-                            info!(
-                                "Added {} (Press T to cycle types, A to add)",
-                                obj_type.display_name()
-                            );
+                    if event.state == ElementState::Pressed {
+                        if let Some(action) = self.keymap.key_action(key, self.modifiers) {
+                            self.perform_action(action);
                         }
-                        KeyCode::KeyT if event.state == ElementState::Pressed => {
-                            // Cycle through object types
-                            self.current_object_type_index =
-                                (self.current_object_type_index + 1) % 15;
-                            let object_types = [
-                                ObjectType::Clock,
-                                ObjectType::Lamp,
-                                ObjectType::Plant,
-                                ObjectType::Coffee,
-                                ObjectType::Laptop,
-                                ObjectType::Notebook,
-                                ObjectType::PenHolder,
-                                ObjectType::Books,
-                                ObjectType::PhotoFrame,
-                                ObjectType::Globe,
-                                ObjectType::Trophy,
-                                ObjectType::Hourglass,
-                                ObjectType::Metronome,
-                                ObjectType::Paper,
-                                ObjectType::Magazine,
-                            ];
-                            info!(
-                                "Selected: {} (Press A to add)",
-                                object_types[self.current_object_type_index].display_name()
-                            );
-                        }
-                        KeyCode::Delete if event.state == ElementState::Pressed => {
-                            // Delete dragged object
-                            if let Some(id) = self.dragging_object_id.take() {
-                                self.state.remove_object(id);
-                                self.object_meshes.remove(&id);
-                                info!("Deleted object");
-                            }
-                        }
-                        KeyCode::Escape if event.state == ElementState::Pressed => {
-                            // Close panels
-                            self.ui_state.close_customization();
-                            self.ui_state.left_sidebar_open = false;
-                        }
-                        _ => {}
                     }
                 }
             }
@@ -851,289 +1246,812 @@ impl App {
         false
     }
 
-    /// Find object at cursor position (without starting drag)
-    fn find_object_at_cursor(&self) -> Option<u64> {
-        let (mx, my) = self.mouse_position;
-        let ndc_x = (2.0 * mx / self.size.width as f32) - 1.0;
-        let ndc_y = 1.0 - (2.0 * my / self.size.height as f32);
+    /// Run an action bound to a key or mouse button press
+    fn perform_action(&mut self, action: Action) {
+        match action {
+            Action::AddObject => {
+                let obj_type = ObjectType::all()[self.current_object_type_index];
+                self.add_object(obj_type);
+                info!(
+                    "Added {} (Press {} to cycle types, {} to add)",
+                    obj_type.display_name(),
+                    self.keymap.label(Action::CycleType),
+                    self.keymap.label(Action::AddObject)
+                );
+            }
+            Action::CycleType => {
+                let object_types = ObjectType::all();
+                self.current_object_type_index = (self.current_object_type_index + 1) % object_types.len();
+                info!(
+                    "Selected: {} (Press {} to add)",
+                    object_types[self.current_object_type_index].display_name(),
+                    self.keymap.label(Action::AddObject)
+                );
+            }
+            Action::DeleteObject => {
+                // Delete dragged object
+                if let Some(id) = self.dragging_object_id.take() {
+                    self.drag_start_position = None;
+                    self.delete_object(id);
+                    info!("Deleted object");
+                }
+            }
+            Action::TogglePalette => self.ui_state.toggle_left_sidebar(),
+            Action::ClosePanels => {
+                self.ui_state.close_customization();
+                self.ui_state.left_sidebar_open = false;
+            }
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::CycleCameraMode => self.set_camera_mode(self.camera.mode.next()),
+            Action::RotateView => self.camera.rotate_view(),
+            Action::ResetView => self.camera.reset(&self.settings.camera),
+            Action::NextViewBookmark => {
+                if self.view_bookmarks.is_empty() {
+                    info!("No saved views yet");
+                } else {
+                    let index = self
+                        .current_bookmark
+                        .map_or(0, |i| (i + 1) % self.view_bookmarks.len());
+                    self.go_to_bookmark(index);
+                }
+            }
+            Action::ViewBookmark(index) => {
+                if index < self.view_bookmarks.len() {
+                    self.go_to_bookmark(index);
+                } else {
+                    info!("No saved view {} yet", index + 1);
+                }
+            }
+            // Pointer actions are handled where their input arrives
+            Action::Drag | Action::ContextClick | Action::Rotate | Action::Scale | Action::CameraDrag => {}
+        }
+    }
 
-        let inv_proj = self.camera.projection_matrix().inverse();
-        let inv_view = self.camera.view_matrix().inverse();
+    /// Drop the dragged object, throwing it with the cursor's recent velocity
+    ///
+    /// The move is recorded once the object and everything it knocks into
+    /// have come to rest.
+    fn end_drag(&mut self) {
+        let Some(id) = self.dragging_object_id.take() else {
+            return;
+        };
+        let from = self.drag_start_position.take();
+        let velocity = self.throw_velocity();
+
+        // An earlier throw that is still moving already holds the start poses
+        let start = self.motion_start.get_or_insert_with(|| {
+            self.state
+                .objects
+                .iter()
+                .filter(|o| o.id != id && !o.is_dragging)
+                .map(|o| (o.id, Vec3::new(o.position.x, o.target_y, o.position.z), o.rotation))
+                .collect()
+        });
 
-        let ray_clip = glam::Vec4::new(ndc_x, ndc_y, -1.0, 1.0);
-        let ray_eye = inv_proj * ray_clip;
-        let ray_eye = glam::Vec4::new(ray_eye.x, ray_eye.y, -1.0, 0.0);
-        let ray_world = (inv_view * ray_eye).truncate().normalize();
+        let objects_clone: Vec<DeskObject> = self.state.objects.clone();
+        if let Some(obj) = self.state.get_object_mut(id) {
+            if let Some(from) = from {
+                if !start.iter().any(|(start_id, ..)| *start_id == id) {
+                    start.push((id, from, obj.rotation));
+                }
+            }
+            self.physics.end_drag(obj, &objects_clone, velocity);
+            self.state.mark_dirty();
+            self.update_object_transform(id);
+        }
+    }
 
-        let ray_origin = self.camera.position;
-        let mut best_id = None;
-        let mut best_dist = f32::MAX;
+    /// Average cursor velocity on the drag plane over the last moments of a
+    /// drag, so a flick throws the object and a pause before letting go
+    /// drops it in place
+    fn throw_velocity(&mut self) -> Vec3 {
+        let now = Instant::now();
+        if let Some(point) = self.drag_plane_point() {
+            self.drag_samples.push_back((now, point));
+        }
+        let samples: Vec<(Instant, Vec3)> = self
+            .drag_samples
+            .drain(..)
+            .filter(|(at, _)| now.duration_since(*at) <= THROW_SAMPLE_WINDOW)
+            .collect();
+        match (samples.first(), samples.last()) {
+            (Some((start, from)), Some((end, to))) if end > start => {
+                (*to - *from) / end.duration_since(*start).as_secs_f32()
+            }
+            _ => Vec3::ZERO,
+        }
+    }
 
-        for obj in &self.state.objects {
-            let to_obj = obj.position - ray_origin;
-            let t = to_obj.dot(ray_world);
-            if t < 0.0 {
+    /// Record where the last throw left every object as one undo step
+    fn finish_motion(&mut self) {
+        let Some(start) = self.motion_start.take() else {
+            return;
+        };
+        let mut commands = Vec::new();
+        for (id, from, from_rotation) in start {
+            let Some(obj) = self.state.get_object(id) else {
                 continue;
+            };
+            if obj.is_dragging {
+                continue;
+            }
+            let to = Vec3::new(obj.position.x, obj.target_y, obj.position.z);
+            if from != to {
+                commands.push(Command::Move { id, from, to });
+            }
+            if from_rotation != obj.rotation {
+                commands.push(Command::Rotate { id, from: from_rotation, to: obj.rotation });
             }
+        }
+        match commands.len() {
+            0 => {}
+            1 => self.history.record(commands.remove(0)),
+            _ => self.history.record(Command::Batch(commands)),
+        }
+    }
 
-            let closest = ray_origin + ray_world * t;
-            let dist = (closest - obj.position).length();
-            let radius = obj.collision_radius() * 1.5;
+    /// Stop all sliding objects so the history reflects where they are
+    fn stop_motion(&mut self) {
+        self.physics.stop_all(&mut self.state.objects);
+        self.finish_motion();
+    }
 
-            if dist < radius && t < best_dist {
-                best_dist = t;
-                best_id = Some(obj.id);
+    /// Switch the camera mode, e.g. from the keyboard or the palette
+    fn set_camera_mode(&mut self, mode: CameraMode) {
+        self.camera.set_mode(mode);
+        info!("Camera mode: {}", mode.display_name());
+    }
+
+    /// Open the customization panel for the object under the cursor, or
+    /// toggle the left sidebar over empty space
+    fn context_click(&mut self) {
+        if let Some(id) = self.find_object_at_cursor() {
+            if let Some(obj) = self.state.get_object(id) {
+                self.ui_state.open_customization(id, obj.color, obj.accent_color);
             }
+        } else {
+            self.ui_state.toggle_left_sidebar();
         }
+    }
 
-        best_id
+    /// Stand a toppled object back up
+    fn right_object(&mut self, id: u64) {
+        if let Some(obj) = self.state.get_object_mut(id) {
+            self.physics.right_object(obj);
+            info!("Stood {} back up", obj.object_type.display_name());
+        }
+        self.update_object_transform(id);
     }
 
-    fn try_pick_object(&mut self) {
+    /// Animate the camera to frame an object
+    fn focus_object(&mut self, id: u64) {
+        let Some(obj) = self.state.get_object(id) else {
+            return;
+        };
+        let height = obj.collision_height();
+        let center = obj.position + Vec3::Y * (height * 0.5);
+        let radius = obj.collision_radius().max(height * 0.5);
+        info!("Focusing {}", obj.object_type.display_name());
+        self.camera.focus_on(center, radius);
+    }
+
+    /// Animate the camera to a saved view
+    fn go_to_bookmark(&mut self, index: usize) {
+        let Some(bookmark) = self.view_bookmarks.get(index) else {
+            return;
+        };
+        if self.camera.animate_to(bookmark.view.clone()) {
+            info!("Moving to view \"{}\"", bookmark.name);
+            self.current_bookmark = Some(index);
+        } else {
+            log::warn!("Saved view \"{}\" is invalid", bookmark.name);
+        }
+    }
+
+    /// World-space picking ray under the cursor, as (origin, direction)
+    fn cursor_ray(&self) -> (Vec3, Vec3) {
         let (mx, my) = self.mouse_position;
         let ndc_x = (2.0 * mx / self.size.width as f32) - 1.0;
         let ndc_y = 1.0 - (2.0 * my / self.size.height as f32);
+        self.camera.screen_ray(ndc_x, ndc_y)
+    }
 
-        let inv_proj = self.camera.projection_matrix().inverse();
-        let inv_view = self.camera.view_matrix().inverse();
-
-        let ray_clip = glam::Vec4::new(ndc_x, ndc_y, -1.0, 1.0);
-        let ray_eye = inv_proj * ray_clip;
-        let ray_eye = glam::Vec4::new(ray_eye.x, ray_eye.y, -1.0, 0.0);
-        let ray_world = (inv_view * ray_eye).truncate().normalize();
-
-        let ray_origin = self.camera.position;
+    /// Find object at cursor position (without starting drag)
+    fn find_object_at_cursor(&self) -> Option<u64> {
+        let (ray_origin, ray_world) = self.cursor_ray();
         let mut best_id = None;
         let mut best_dist = f32::MAX;
 
         for obj in &self.state.objects {
-            let to_obj = obj.position - ray_origin;
-            let t = to_obj.dot(ray_world);
-            if t < 0.0 {
+            let Some(t) = self.physics.ray_hit(obj, ray_origin, ray_world) else {
                 continue;
-            }
-
-            let closest = ray_origin + ray_world * t;
-            let dist = (closest - obj.position).length();
-            let radius = obj.collision_radius() * 1.5;
+            };
 
-            if dist < radius && t < best_dist {
+            if t < best_dist {
                 best_dist = t;
                 best_id = Some(obj.id);
             }
         }
 
-        if let Some(id) = best_id {
+        best_id
+    }
+
+    fn try_pick_object(&mut self) {
+        if let Some(id) = self.find_object_at_cursor() {
             self.dragging_object_id = Some(id);
+            self.drag_samples.clear();
             if let Some(obj) = self.state.get_object_mut(id) {
-                obj.is_dragging = true;
+                self.physics.start_drag(obj);
+                self.drag_start_position =
+                    Some(Vec3::new(obj.position.x, obj.target_y, obj.position.z));
             }
         }
     }
 
-    fn update_drag(&mut self) {
-        let (mx, my) = self.mouse_position;
-        let ndc_x = (2.0 * mx / self.size.width as f32) - 1.0;
-        let ndc_y = 1.0 - (2.0 * my / self.size.height as f32);
+    /// Point under the cursor on the plane held objects are dragged along
+    fn drag_plane_point(&self) -> Option<Vec3> {
+        let (ray_origin, ray_world) = self.cursor_ray();
 
-        let inv_proj = self.camera.projection_matrix().inverse();
-        let inv_view = self.camera.view_matrix().inverse();
+        let desk_y = self.physics.desk_surface_y();
+        let plane_y = desk_y + self.settings.physics.lift_height;
 
-        let ray_clip = glam::Vec4::new(ndc_x, ndc_y, -1.0, 1.0);
-        let ray_eye = inv_proj * ray_clip;
-        let ray_eye = glam::Vec4::new(ray_eye.x, ray_eye.y, -1.0, 0.0);
-        let ray_world = (inv_view * ray_eye).truncate().normalize();
+        physics::ray_plane_intersection(ray_origin, ray_world, Vec3::new(0.0, plane_y, 0.0), Vec3::Y)
+    }
+
+    fn update_drag(&mut self) {
+        if let Some(intersection) = self.drag_plane_point() {
+            let now = Instant::now();
+            self.drag_samples.push_back((now, intersection));
+            while self
+                .drag_samples
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) > THROW_SAMPLE_WINDOW)
+            {
+                self.drag_samples.pop_front();
+            }
 
-        let desk_y = self.physics.desk_surface_y();
-        let plane_y = desk_y + 0.5;
-
-        if let Some(intersection) = physics::ray_plane_intersection(
-            self.camera.position,
-            ray_world,
-            Vec3::new(0.0, plane_y, 0.0),
-            Vec3::Y,
-        ) {
             if let Some(id) = self.dragging_object_id {
                 if let Some(obj) = self.state.get_object_mut(id) {
-                    obj.position.x = intersection.x.clamp(-4.5, 4.5);
-                    obj.position.z = intersection.z.clamp(-3.0, 3.0);
-                    obj.position.y = plane_y;
+                    self.physics.update_dragging(obj, intersection);
                     self.update_object_transform(id);
                 }
             }
         }
     }
 
+    /// Save the current layout and load another one in its place
+    fn switch_layout(&mut self, name: &str) {
+        if name == self.layouts.active_name() {
+            return;
+        }
+        let Some(path) = self.layouts.path_of(name) else {
+            self.ui_state.layout_error = Some(format!("layout \"{}\" does not exist", name));
+            return;
+        };
+
+        self.store_view_state();
+        self.autosaver.flush(&mut self.state);
+        if self.state.is_dirty() {
+            if let Err(e) = self.save_state() {
+                log::error!("Failed to save state: {}", e);
+                self.ui_state.layout_error = Some(format!("could not save current layout: {}", e));
+                return;
+            }
+        }
+        if let Err(e) = self.layouts.set_active(name) {
+            log::warn!("Could not remember active layout: {}", e);
+        }
+
+        self.state = AppState::load_from(path);
+        self.on_state_replaced();
+        self.ui_state.layout_finished();
+        info!("Switched to layout \"{}\"", name);
+    }
+
+    /// Create a new layout, either empty or as a copy of the current desk,
+    /// and switch to it
+    fn create_layout(&mut self, name: &str, duplicate: bool) {
+        self.store_view_state();
+        self.autosaver.flush(&mut self.state);
+        if self.state.is_dirty() {
+            if let Err(e) = self.save_state() {
+                log::error!("Failed to save state: {}", e);
+            }
+        }
+
+        let path = match self.layouts.create(name) {
+            Ok(path) => path,
+            Err(e) => {
+                self.ui_state.layout_error = Some(e);
+                return;
+            }
+        };
+
+        let mut new_state = if duplicate {
+            self.state.clone()
+        } else {
+            AppState::empty_at(path.clone())
+        };
+        new_state.set_file_path(path);
+        new_state.mark_dirty();
+        self.state = new_state;
+        if let Err(e) = self.save_state() {
+            log::error!("Failed to save new layout: {}", e);
+        }
+
+        if let Err(e) = self.layouts.set_active(name.trim()) {
+            log::warn!("Could not remember active layout: {}", e);
+        }
+        self.on_state_replaced();
+        self.ui_state.layout_finished();
+        info!("Created layout \"{}\"", name.trim());
+    }
+
+    /// Reset transient interaction state and GPU meshes after `self.state`
+    /// has been swapped for another desk
+    fn on_state_replaced(&mut self) {
+        self.dragging_object_id = None;
+        self.drag_start_position = None;
+        self.motion_start = None;
+        self.interpolated_ids.clear();
+        self.history.clear();
+        self.autosaver.watch(&self.state);
+        self.ui_state.external_change_pending = false;
+        self.drag_button = None;
+        self.ui_state.close_customization();
+        self.settle_loaded_state();
+        self.restore_view_state();
+        self.rebuild_object_meshes();
+    }
+
+    /// React to the state file being modified by another program
+    ///
+    /// Without unsaved in-app edits the file is reloaded right away; otherwise
+    /// autosave is paused and the user is asked which version to keep.
+    fn on_external_change(&mut self) {
+        if self.state.is_dirty() || self.dragging_object_id.is_some() {
+            info!("State file changed on disk while there are unsaved changes");
+            self.autosaver.set_suspended(true);
+            self.ui_state.external_change_pending = true;
+        } else {
+            self.reload_from_disk();
+        }
+    }
+
+    /// Replace the desk with the state file's current content, rebuilding only
+    /// the meshes of objects whose type or colors changed
+    fn reload_from_disk(&mut self) {
+        let Some(path) = self.state.file_path().map(PathBuf::from) else {
+            return;
+        };
+
+        let previous: HashMap<u64, DeskObject> = self
+            .state
+            .objects
+            .drain(..)
+            .map(|obj| (obj.id, obj))
+            .collect();
+        self.state = AppState::load_from(path);
+        self.settle_loaded_state();
+
+        let mut rebuilt = 0;
+        let objects = self.state.objects.clone();
+        for obj in &objects {
+            match previous.get(&obj.id) {
+                Some(old)
+                    if old.object_type == obj.object_type
+                        && old.color == obj.color
+                        && old.accent_color == obj.accent_color
+                        && self.object_meshes.contains_key(&obj.id) =>
+                {
+                    self.update_object_transform(obj.id);
+                }
+                _ => {
+                    self.create_object_mesh(obj);
+                    rebuilt += 1;
+                }
+            }
+        }
+        self.object_meshes
+            .retain(|id, _| objects.iter().any(|o| o.id == *id));
+
+        self.dragging_object_id = None;
+        self.drag_start_position = None;
+        self.motion_start = None;
+        self.interpolated_ids.clear();
+        self.history.clear();
+        self.autosaver.watch(&self.state);
+        let selected_exists = self
+            .ui_state
+            .selected_object_id
+            .is_some_and(|id| self.state.get_object(id).is_some());
+        if !selected_exists {
+            self.ui_state.close_customization();
+        }
+        info!(
+            "Reloaded state file changed on disk ({} objects, {} meshes rebuilt)",
+            objects.len(),
+            rebuilt
+        );
+    }
+
+    /// Snapshot the camera pose, panel layout and window placement
+    fn capture_view(&self) -> ViewState {
+        let position = self.window.outer_position().ok().map(|p| (p.x, p.y));
+        let (camera_position, yaw, pitch) = self.camera.perspective_pose();
+        ViewState {
+            camera: Some(CameraPose {
+                position: camera_position,
+                yaw,
+                pitch,
+                fov: self.camera.fov.to_degrees(),
+                mode: self.camera.mode,
+            }),
+            bookmarks: self.view_bookmarks.clone(),
+            left_sidebar_open: self.ui_state.left_sidebar_open,
+            expanded_categories: self.ui_state.expanded_categories(),
+            // Keep the windowed placement while fullscreen
+            window: if self.window.fullscreen().is_some() {
+                self.state.view.as_ref().and_then(|v| v.window.clone())
+            } else {
+                Some(WindowGeometry {
+                    width: self.size.width,
+                    height: self.size.height,
+                    position,
+                    maximized: self.window.is_maximized(),
+                })
+            },
+        }
+    }
+
+    /// Store the current view in the state, marking it dirty if it changed
+    fn store_view_state(&mut self) {
+        let view = self.capture_view();
+        if self.state.view.as_ref() != Some(&view) {
+            self.state.view = Some(view);
+            self.state.mark_dirty();
+        }
+    }
+
+    /// Apply the camera pose and panel layout saved with the state
+    ///
+    /// Window placement is only applied when the window is created.
+    fn restore_view_state(&mut self) {
+        self.current_bookmark = None;
+        self.view_bookmarks = self
+            .state
+            .view
+            .as_ref()
+            .map(|v| v.bookmarks.clone())
+            .unwrap_or_default();
+        let Some(view) = self.state.view.clone() else {
+            return;
+        };
+        if let Some(pose) = &view.camera {
+            if !self.camera.apply_pose(pose.position, pose.yaw, pose.pitch, pose.fov) {
+                log::warn!("Ignoring invalid saved camera pose");
+            }
+            self.camera.set_mode(pose.mode);
+        }
+        self.ui_state.left_sidebar_open = view.left_sidebar_open;
+        self.ui_state.set_expanded_categories(&view.expanded_categories);
+    }
+
+    /// Apply the loaded state's collision settings to the physics engine and
+    /// restore the transient resting heights of its objects
+    fn settle_loaded_state(&mut self) {
+        self.physics.collision_radius_multiplier = self.state.collision_radius_multiplier;
+        self.physics.collision_height_multiplier = self.state.collision_height_multiplier;
+        self.physics.settle_objects(&mut self.state.objects);
+    }
+
+    /// Apply edited settings to the running app and schedule saving them
+    ///
+    /// Only the parts that changed are rebuilt: the desk mesh and physics
+    /// bounds for desk edits, the floor mesh for its color, the lighting
+    /// uniform for color and lighting edits, the camera projection for
+    /// camera edits, and the scene targets for pixelation edits.
+    fn apply_settings(&mut self, settings: Config) {
+        let previous = std::mem::replace(&mut self.settings, settings);
+
+        if previous.camera != self.settings.camera {
+            self.camera.apply_config(&self.settings.camera);
+        }
+        if previous.desk != self.settings.desk {
+            self.desk_mesh = Self::create_desk_mesh(&self.device, &self.settings.desk);
+        }
+        if previous.desk != self.settings.desk || previous.colors.ground != self.settings.colors.ground {
+            self.floor_mesh =
+                Self::create_floor_mesh(&self.device, &self.settings.desk, self.settings.colors.ground);
+        }
+        if previous.colors != self.settings.colors || previous.lighting != self.settings.lighting {
+            let lighting_uniform = LightingUniform::from_config(&self.settings);
+            self.queue
+                .write_buffer(&self.lighting_buffer, 0, bytemuck::cast_slice(&[lighting_uniform]));
+        }
+        if previous.desk != self.settings.desk || previous.physics != self.settings.physics {
+            self.physics.apply_config(&self.settings);
+        }
+        if previous.pixelation != self.settings.pixelation {
+            let size = (self.config.width, self.config.height);
+            self.pixelation.resize(&self.device, size, &self.settings.pixelation);
+        }
+        if previous.desk != self.settings.desk {
+            self.camera.frame_desk(&self.physics.desk);
+        }
+        if previous.bindings != self.settings.bindings {
+            self.keymap = KeyMap::from_config(&self.settings.bindings);
+            for conflict in self.keymap.conflicts() {
+                log::warn!("Bindings: {}", conflict);
+            }
+            self.ui_state.control_hints = self.keymap.describe();
+        }
+
+        // Keep objects on a resized desk and resting on its surface
+        let desk_resized = Desk::from_config(&previous.desk) != self.physics.desk;
+        if desk_resized && self.dragging_object_id.is_none() {
+            let before: Vec<Vec3> = self.state.objects.iter().map(|o| o.position).collect();
+            self.physics.settle_objects(&mut self.state.objects);
+            let ids: Vec<u64> = self.state.objects.iter().map(|o| o.id).collect();
+            for id in ids {
+                self.update_object_transform(id);
+            }
+            if self.state.objects.iter().map(|o| o.position).ne(before) {
+                self.state.mark_dirty();
+            }
+        }
+
+        self.settings_changed_at = Some(Instant::now());
+    }
+
+    /// Apply a theme's scene colors, palettes and UI colors
+    fn switch_theme(&mut self, name: &str) {
+        let Some(theme) = self.themes.get(name).cloned() else {
+            log::warn!("Unknown theme \"{}\"", name);
+            return;
+        };
+        let mut settings = self.settings.clone();
+        theme.apply_to(&mut settings);
+        self.apply_settings(settings);
+
+        let mut style = (*self.egui_ctx.style()).clone();
+        style.visuals = theme.visuals();
+        self.egui_ctx.set_style(style);
+        self.ui_state.set_theme(&theme);
+        info!("Switched to theme \"{}\"", name);
+    }
+
+    /// Write the settings to the user config file
+    fn save_settings(&mut self) {
+        self.settings_changed_at = None;
+        self.ui_state.config_status = Some(match self.settings.save() {
+            Ok(path) => {
+                info!("Saved settings to {:?}", path);
+                format!("Settings saved to {}", path.display())
+            }
+            Err(e) => {
+                log::error!("Failed to save settings: {}", e);
+                format!("Could not save settings: {}", e)
+            }
+        });
+    }
+
     fn add_object(&mut self, object_type: ObjectType) {
         let id = self.state.next_id();
-        let desk_y = self.physics.desk_surface_y();
-        let position = Vec3::new(
-            rand::random::<f32>() * 4.0 - 2.0,
-            desk_y,
-            rand::random::<f32>() * 3.0 - 1.5,
-        );
+
+        // Pick a free spot anywhere on the desk
+        let probe = DeskObject::new(id, object_type, Vec3::ZERO);
+        let radius = probe.collision_radius() * self.physics.collision_radius_multiplier;
+        let target = self.physics.desk.random_position(radius);
+        let position = self.physics.find_valid_position(target, &probe, &self.state.objects);
+        let position = Vec3::new(position.x, self.physics.desk_surface_y(), position.z);
         let object = DeskObject::new(id, object_type, position);
         self.create_object_mesh(&object);
+        self.history.record(Command::Add(object.clone()));
         self.state.add_object(object);
     }
 
-    fn save_state(&self) -> Result<(), Box<dyn std::error::Error>> {
-        self.state.save()
+    /// Remove an object, recording the removal for undo
+    fn delete_object(&mut self, id: u64) {
+        let Some(index) = self.state.objects.iter().position(|o| o.id == id) else {
+            return;
+        };
+        if let Some(mut object) = self.state.remove_object(id) {
+            // Objects deleted mid-drag come back resting when undone
+            object.is_dragging = false;
+            self.history.record(Command::Remove { object, index });
+        }
+        self.object_meshes.remove(&id);
     }
 
-    fn create_depth_texture(
-        device: &wgpu::Device,
-        config: &wgpu::SurfaceConfiguration,
-    ) -> wgpu::TextureView {
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Depth Texture"),
-            size: wgpu::Extent3d {
-                width: config.width,
-                height: config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
+    /// Change an object's colors and rebuild its mesh, recording the change
+    /// for undo
+    fn recolor_object(&mut self, id: u64, color: u32, accent_color: u32) {
+        let Some(obj) = self.state.get_object_mut(id) else {
+            return;
+        };
+        let from = (obj.color, obj.accent_color);
+        obj.color = color;
+        obj.accent_color = accent_color;
+        self.state.mark_dirty();
+        self.history.record(Command::Recolor {
+            id,
+            from,
+            to: (color, accent_color),
         });
-        texture.create_view(&wgpu::TextureViewDescriptor::default())
+
+        // Rebuild mesh with new color
+        if let Some(obj) = self.state.get_object(id).cloned() {
+            self.object_meshes.remove(&id);
+            self.create_object_mesh(&obj);
+        }
     }
 
-    fn create_desk_mesh(device: &wgpu::Device) -> GpuMesh {
-        let (r, g, b) = hex_to_rgb(CONFIG.desk.color);
-        let hw = CONFIG.desk.width / 2.0;
-        let hd = CONFIG.desk.depth / 2.0;
-        let h = CONFIG.desk.height;
+    /// Undo the last desk edit
+    fn undo(&mut self) {
+        if self.dragging_object_id.is_some() {
+            return;
+        }
+        self.stop_motion();
+        if self.history.undo(&mut self.state) {
+            self.after_history_step();
+            info!("Undo");
+        }
+    }
 
-        let vertices = vec![
-            // Top
-            Vertex {
-                position: [-hw, h, -hd],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-            Vertex {
-                position: [hw, h, -hd],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-            Vertex {
-                position: [hw, h, hd],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-            Vertex {
-                position: [-hw, h, hd],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-            // Front
-            Vertex {
-                position: [-hw, 0.0, hd],
-                normal: [0.0, 0.0, 1.0],
-                color: [r * 0.8, g * 0.8, b * 0.8, 1.0],
-            },
-            Vertex {
-                position: [hw, 0.0, hd],
-                normal: [0.0, 0.0, 1.0],
-                color: [r * 0.8, g * 0.8, b * 0.8, 1.0],
-            },
-            Vertex {
-                position: [hw, h, hd],
-                normal: [0.0, 0.0, 1.0],
-                color: [r * 0.8, g * 0.8, b * 0.8, 1.0],
-            },
-            Vertex {
-                position: [-hw, h, hd],
-                normal: [0.0, 0.0, 1.0],
-                color: [r * 0.8, g * 0.8, b * 0.8, 1.0],
-            },
-        ];
+    /// Redo the last undone desk edit
+    fn redo(&mut self) {
+        if self.dragging_object_id.is_some() {
+            return;
+        }
+        self.stop_motion();
+        if self.history.redo(&mut self.state) {
+            self.after_history_step();
+            info!("Redo");
+        }
+    }
 
-        let indices: Vec<u16> = vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7];
+    /// Bring meshes and panels in line with the state after undo/redo
+    fn after_history_step(&mut self) {
+        self.interpolated_ids.clear();
+        let selected_exists = self
+            .ui_state
+            .selected_object_id
+            .is_some_and(|id| self.state.get_object(id).is_some());
+        if !selected_exists {
+            self.ui_state.close_customization();
+        }
+        self.rebuild_object_meshes();
+    }
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Desk Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+    fn save_state(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.autosaver.save_now(&mut self.state)
+    }
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Desk Index Buffer"),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+    fn create_desk_mesh(device: &wgpu::Device, desk: &DeskConfig) -> GpuMesh {
+        let mesh_data = mesh::create_desk(&Desk::from_config(desk), desk.color);
+        GpuMesh::from_mesh_data(device, &mesh_data)
+    }
 
-        GpuMesh {
-            vertex_buffer,
-            index_buffer,
-            num_indices: indices.len() as u32,
-        }
+    fn create_floor_mesh(device: &wgpu::Device, desk: &DeskConfig, ground_color: u32) -> GpuMesh {
+        let mesh_data = mesh::create_floor(&Desk::from_config(desk), ground_color);
+        GpuMesh::from_mesh_data(device, &mesh_data)
     }
+}
 
-    fn create_floor_mesh(device: &wgpu::Device) -> GpuMesh {
-        let (r, g, b) = hex_to_rgb(CONFIG.colors.ground);
-        let s = 50.0;
+/// Quiet time after the last settings change before the config file is
+/// written, so dragging a slider doesn't write on every frame
+const SETTINGS_SAVE_DELAY: Duration = Duration::from_secs(1);
 
-        let vertices = vec![
-            Vertex {
-                position: [-s, 0.0, -s],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-            Vertex {
-                position: [s, 0.0, -s],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-            Vertex {
-                position: [s, 0.0, s],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-            Vertex {
-                position: [-s, 0.0, s],
-                normal: [0.0, 1.0, 0.0],
-                color: [r, g, b, 1.0],
-            },
-        ];
+/// Length of one physics step in seconds
+const PHYSICS_STEP: f32 = 1.0 / 120.0;
 
-        let indices: Vec<u16> = vec![0, 1, 2, 0, 2, 3];
+/// Longest frame time the simulation catches up on, so a stalled frame
+/// doesn't freeze the app in a burst of steps
+const MAX_FRAME_TIME: f32 = 0.25;
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Floor Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+/// How often an idle window still updates, for autosave and file watching
+const IDLE_FRAME_INTERVAL: Duration = Duration::from_millis(250);
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Floor Index Buffer"),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+/// How far back cursor movement counts towards the velocity of a throw
+const THROW_SAMPLE_WINDOW: Duration = Duration::from_millis(100);
 
-        GpuMesh {
-            vertex_buffer,
-            index_buffer,
-            num_indices: indices.len() as u32,
+/// Longest gap between two clicks on an object that counts as a double-click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Distance in pixels the cursor must move with the context button held
+/// before the press becomes a camera drag instead of a click
+const CONTEXT_DRAG_THRESHOLD: f32 = 5.0;
+
+/// Smallest window size restored from saved state
+const MIN_WINDOW_SIZE: (u32, u32) = (640, 400);
+
+/// Build the main window attributes, restoring the saved size and position
+/// when they still fit the connected monitors
+///
+/// A size or fullscreen mode given on the command line takes precedence over
+/// the saved placement.
+fn window_attributes(event_loop: &ActiveEventLoop, saved: Option<&WindowGeometry>, cli: &Cli) -> WindowAttributes {
+    let attrs = WindowAttributes::default()
+        .with_title("Focus Desktop Simulator")
+        .with_inner_size(winit::dpi::LogicalSize::new(1280, 720));
+
+    if cli.fullscreen {
+        return attrs.with_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
+    if let Some((width, height)) = cli.window_size() {
+        return attrs.with_inner_size(winit::dpi::LogicalSize::new(width, height));
+    }
+    let Some(saved) = saved else {
+        return attrs;
+    };
+
+    let monitors: Vec<_> = event_loop.available_monitors().collect();
+    let fits_a_monitor = monitors.is_empty()
+        || monitors
+            .iter()
+            .any(|m| saved.width <= m.size().width && saved.height <= m.size().height);
+    if saved.width < MIN_WINDOW_SIZE.0 || saved.height < MIN_WINDOW_SIZE.1 || !fits_a_monitor {
+        log::info!("Saved window size no longer fits the screen, using default");
+        return attrs;
+    }
+    let mut attrs = attrs
+        .with_inner_size(PhysicalSize::new(saved.width, saved.height))
+        .with_maximized(saved.maximized);
+
+    // Only restore the position if the title bar area lands on a monitor
+    if let Some((x, y)) = saved.position {
+        let grab_point = (x + 100, y + 20);
+        let visible = monitors.iter().any(|m| {
+            let origin = m.position();
+            let size = m.size();
+            grab_point.0 >= origin.x
+                && grab_point.1 >= origin.y
+                && grab_point.0 < origin.x + size.width as i32
+                && grab_point.1 < origin.y + size.height as i32
+        });
+        if visible {
+            attrs = attrs.with_position(PhysicalPosition::new(x, y));
+        } else {
+            log::info!("Saved window position is off-screen, letting the system place it");
         }
     }
+
+    attrs
 }
 
 /// Application wrapper for winit 0.30 ApplicationHandler
 struct AppWrapper {
     app: Option<App>,
+    /// Config loaded at startup, handed to the app once it is created
+    settings: Option<Config>,
+    /// Command-line options for the window and state file
+    cli: Cli,
 }
 
 impl ApplicationHandler for AppWrapper {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.app.is_none() {
-            let window_attrs = WindowAttributes::default()
-                .with_title("Focus Desktop Simulator")
-                .with_inner_size(winit::dpi::LogicalSize::new(1280, 720));
+            let settings = self.settings.take().unwrap_or_else(Config::load);
+
+            // Load state of the last used layout, or the file given on the
+            // command line
+            let layouts = cli::load_layouts();
+            let state_path = match &self.cli.state {
+                Some(path) => {
+                    info!("Using state file {:?}", path);
+                    path.clone()
+                }
+                None => {
+                    info!("Using layout \"{}\"", layouts.active_name());
+                    layouts.active_path()
+                }
+            };
+            let app_state = AppState::load_from(state_path);
+
+            let saved_window = app_state.view.as_ref().and_then(|v| v.window.as_ref());
+            let window_attrs = window_attributes(event_loop, saved_window, &self.cli);
 
             let window = Arc::new(
                 event_loop
@@ -1141,7 +2059,10 @@ impl ApplicationHandler for AppWrapper {
                     .expect("Failed to create window"),
             );
 
-            self.app = Some(pollster::block_on(App::new(window)).expect("Failed to create app"));
+            let mut app = pollster::block_on(App::new(window, settings, layouts, app_state))
+                .expect("Failed to create app");
+            app.ui_state.state_file_override = self.cli.state.as_ref().map(|path| path.display().to_string());
+            self.app = Some(app);
             info!("Application initialized");
         }
     }
@@ -1150,11 +2071,34 @@ impl ApplicationHandler for AppWrapper {
         let Some(app) = &mut self.app else { return };
 
         let _egui_consumed = app.handle_event(&event);
+        // Any input may change what is drawn
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            app.window.request_redraw();
+        }
 
         match event {
             WindowEvent::CloseRequested => {
-                info!("Saving state and exiting...");
-                let _ = app.save_state();
+                if app.settings_changed_at.is_some() {
+                    app.save_settings();
+                }
+                app.store_view_state();
+                // Let a running autosave finish writing before the process
+                // exits; if it failed the state is dirty again and saved below
+                app.autosaver.flush(&mut app.state);
+                if app.ui_state.external_change_pending {
+                    // Don't clobber the external edit; keep ours next to it
+                    match app.state.save_conflict_copy() {
+                        Ok(path) => info!("Saved unsaved changes to {:?} and exiting...", path),
+                        Err(e) => log::error!("Failed to save conflict copy: {}", e),
+                    }
+                } else if app.state.is_dirty() {
+                    info!("Saving state and exiting...");
+                    if let Err(e) = app.save_state() {
+                        log::error!("Failed to save state: {}", e);
+                    }
+                } else {
+                    info!("No unsaved changes, exiting...");
+                }
                 event_loop.exit();
             }
             WindowEvent::Resized(size) => app.resize(size),
@@ -1172,34 +2116,75 @@ impl ApplicationHandler for AppWrapper {
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        if let Some(app) = &self.app {
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let (Some(app), StartCause::ResumeTimeReached { .. }) = (&self.app, cause) {
             app.window.request_redraw();
         }
     }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(app) = &self.app else { return };
+        // Render continuously only while something moves; otherwise sleep
+        // until input arrives or the next idle frame is due
+        match app.next_frame_at() {
+            None => {
+                event_loop.set_control_flow(ControlFlow::Poll);
+                app.window.request_redraw();
+            }
+            Some(at) => event_loop.set_control_flow(ControlFlow::WaitUntil(at)),
+        }
+    }
 }
 
 fn main() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp_millis()
-        .init();
+    let cli = Cli::parse();
+
+    let mut logger = match &cli.log_level {
+        Some(filter) => {
+            let mut builder = env_logger::Builder::new();
+            builder.parse_filters(filter);
+            builder
+        }
+        None => env_logger::Builder::from_env(
+            env_logger::Env::default().default_filter_or(cli.default_log_filter()),
+        ),
+    };
+    logger.format_timestamp_millis().init();
+
+    if let Some(path) = &cli.config {
+        config::set_config_file_path(path.clone());
+    }
+    let settings = Config::load();
+
+    if let Some(command) = &cli.command {
+        if let Err(e) = cli::run(&cli, command, &settings) {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(name) = &cli.layout {
+        if let Err(e) = cli::load_layouts().set_active(name) {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
 
     info!("Starting Focus Desktop Simulator...");
     info!("Controls:");
     info!("  Click Menu button (top-left) - Open object palette");
-    info!("  Right-click on object - Open customization panel");
-    info!("  Right-click on empty space - Toggle palette");
-    info!("  Click+Drag - Move object");
-    info!("  Scroll - Rotate object");
-    info!("  Shift+Scroll - Scale object");
-    info!("  Delete - Delete dragged object");
-    info!("  Escape - Close panels");
-    info!("  T - Cycle through object types (keyboard shortcut)");
-    info!("  A - Add selected object (keyboard shortcut)");
+    for line in KeyMap::from_config(&settings.bindings).describe() {
+        info!("  {}", line);
+    }
 
     let event_loop = EventLoop::new().expect("Failed to create event loop");
     event_loop.set_control_flow(ControlFlow::Poll);
 
-    let mut app_wrapper = AppWrapper { app: None };
+    let mut app_wrapper = AppWrapper {
+        app: None,
+        settings: Some(settings),
+        cli,
+    };
     event_loop.run_app(&mut app_wrapper).expect("Event loop error");
 }
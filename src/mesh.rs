@@ -2,7 +2,8 @@
 //!
 //! Creates 3D meshes for each object type with proper geometry.
 
-use crate::config::hex_to_rgb;
+use crate::config::{hex_to_rgb, DeskShape};
+use crate::desk::Desk;
 use crate::desk_object::ObjectType;
 use std::f32::consts::PI;
 
@@ -805,3 +806,71 @@ pub fn generate_object_mesh(object_type: ObjectType, main_color: u32, accent_col
         ObjectType::Magazine => create_magazine(main_color, accent_color),
     }
 }
+
+/// Create the desk mesh: the top surface and its side walls down to the floor
+pub fn create_desk(desk: &Desk, color: u32) -> MeshData {
+    let mut mesh = MeshData::new();
+    let (r, g, b) = hex_to_rgb(color);
+    let top_color = [r, g, b, 1.0];
+    let side_color = [r * 0.8, g * 0.8, b * 0.8, 1.0];
+    let h = desk.surface_y;
+    let up = [0.0, 1.0, 0.0];
+    let top = |x: f32, z: f32| Vertex {
+        position: [x, h, z],
+        normal: up,
+        color: top_color,
+    };
+
+    // Top surface
+    match desk.shape {
+        DeskShape::Round => {
+            let outline = desk.outline();
+            for i in 0..outline.len() {
+                let (x0, z0) = outline[i];
+                let (x1, z1) = outline[(i + 1) % outline.len()];
+                mesh.add_triangle(top(0.0, 0.0), top(x0, z0), top(x1, z1));
+            }
+        }
+        _ => {
+            for (min_x, max_x, min_z, max_z) in desk.rects() {
+                mesh.add_quad(
+                    top(min_x, max_z),
+                    top(max_x, max_z),
+                    top(max_x, min_z),
+                    top(min_x, min_z),
+                );
+            }
+        }
+    }
+
+    // Side walls, one per outline edge
+    let outline = desk.outline();
+    for i in 0..outline.len() {
+        let (x0, z0) = outline[i];
+        let (x1, z1) = outline[(i + 1) % outline.len()];
+        let length = ((x1 - x0).powi(2) + (z1 - z0).powi(2)).sqrt().max(1e-6);
+        let normal = [-(z1 - z0) / length, 0.0, (x1 - x0) / length];
+        let side = |x: f32, y: f32, z: f32| Vertex {
+            position: [x, y, z],
+            normal,
+            color: side_color,
+        };
+        mesh.add_quad(side(x0, 0.0, z0), side(x1, 0.0, z1), side(x1, h, z1), side(x0, h, z0));
+    }
+
+    mesh
+}
+
+/// Create the floor: a square on the ground large enough to surround the desk
+pub fn create_floor(desk: &Desk, color: u32) -> MeshData {
+    let mut mesh = MeshData::new();
+    let (r, g, b) = hex_to_rgb(color);
+    let s = (desk.half_width.max(desk.half_depth) * 5.0).max(50.0);
+    let corner = |x: f32, z: f32| Vertex {
+        position: [x, 0.0, z],
+        normal: [0.0, 1.0, 0.0],
+        color: [r, g, b, 1.0],
+    };
+    mesh.add_quad(corner(-s, s), corner(s, s), corner(s, -s), corner(-s, -s));
+    mesh
+}
@@ -2,9 +2,11 @@
 //!
 //! Handles collision detection, object dropping, and stacking.
 
-use glam::Vec3;
-use crate::config::CONFIG;
-use crate::desk_object::DeskObject;
+use glam::{Quat, Vec3};
+use std::f32::consts::FRAC_PI_2;
+use crate::config::Config;
+use crate::desk::Desk;
+use crate::desk_object::{CollisionShape, DeskObject};
 
 /// Physics state for an object
 #[derive(Debug, Clone, Default)]
@@ -19,6 +21,244 @@ pub struct ObjectPhysicsState {
     pub tilt_velocity: Vec3,
 }
 
+impl ObjectPhysicsState {
+    /// Whether the object is neither sliding, spinning nor wobbling
+    pub fn is_resting(&self) -> bool {
+        self.velocity == Vec3::ZERO
+            && self.angular_velocity == 0.0
+            && self.tilt_velocity == Vec3::ZERO
+            && (self.tilt == Vec3::ZERO || self.is_toppled())
+    }
+
+    /// Whether the object has tipped over and lies on its side
+    pub fn is_toppled(&self) -> bool {
+        self.tilt.length() >= TOPPLED_ANGLE - 1e-4
+    }
+}
+
+/// Tilt (rotation around X and Z) that leans an object's top towards the
+/// given horizontal direction, by the direction's length in radians
+pub fn tilt_towards(direction: Vec3) -> Vec3 {
+    Vec3::new(direction.z, 0.0, -direction.x)
+}
+
+/// Deceleration of a sliding object at friction 1, in units per second squared
+const SLIDE_DECELERATION: f32 = 9.0;
+
+/// Speed below which a sliding object stops, in units per second
+const REST_SPEED: f32 = 0.05;
+
+/// Spin below which a spinning object stops, in radians per second
+const REST_SPIN: f32 = 0.1;
+
+/// Fastest speed a released object keeps, in units per second
+const MAX_RELEASE_SPEED: f32 = 8.0;
+
+/// Spin picked up in a glancing collision, in radians per unit of sideways speed
+const COLLISION_SPIN: f32 = 2.0;
+
+/// Vertical overlap below which two objects count as stacked rather than colliding
+const STACK_TOLERANCE: f32 = 0.01;
+
+/// Y position of the floor around the desk
+const FLOOR_Y: f32 = 0.0;
+
+/// Tilt of an object lying on its side
+const TOPPLED_ANGLE: f32 = FRAC_PI_2;
+
+/// Tilt past which an object of stability 1 would fall over, in radians;
+/// less stable objects fall over at proportionally smaller tilts
+const MAX_TIP_ANGLE: f32 = 1.0;
+
+/// Fraction of its tilt speed an object keeps when it rocks back through upright
+const WOBBLE_RESTITUTION: f32 = 0.5;
+
+/// Tilt below which a wobbling object stands still, in radians
+const REST_TILT: f32 = 0.01;
+
+/// Tilt speed below which a wobbling object stands still, in radians per second
+const REST_TILT_SPEED: f32 = 0.2;
+
+/// How strongly a collision tips an object, scaled by its instability
+const TIP_RESPONSE: f32 = 2.0;
+
+/// Tilt speed an unstable object gets when resting fully off-center on
+/// another, in radians per second
+const OVERHANG_TIP_SPEED: f32 = 4.0;
+
+/// Extra room around an object's footprint that still picks it under the cursor
+const PICK_MARGIN: f32 = 0.03;
+
+/// An object's collision shape placed on the desk, turned with the object
+/// around the vertical axis
+#[derive(Debug, Clone, Copy)]
+pub struct Footprint {
+    /// Center of the footprint (only X and Z are used)
+    pub center: Vec3,
+    /// Size and kind of the shape
+    pub shape: CollisionShape,
+    /// The object's local X axis, flattened onto the desk
+    axis_x: Vec3,
+    /// The object's local Z axis, flattened onto the desk
+    axis_z: Vec3,
+}
+
+impl Footprint {
+    /// Place `shape` at `center`, turned by the heading of `rotation`
+    pub fn new(center: Vec3, shape: CollisionShape, rotation: Quat) -> Self {
+        let heading = rotation * Vec3::X;
+        let flat = Vec3::new(heading.x, 0.0, heading.z);
+        let axis_x = if flat.length_squared() > 1e-6 { flat.normalize() } else { Vec3::X };
+        let axis_z = Vec3::new(-axis_x.z, 0.0, axis_x.x);
+        Self { center, shape, axis_x, axis_z }
+    }
+
+    /// The footprint with `margin` added on every side
+    pub fn grown(&self, margin: f32) -> Self {
+        Self { shape: self.shape.grown(margin), ..*self }
+    }
+
+    /// Distance from the center to the edge of the footprint along a
+    /// horizontal unit direction
+    pub fn extent_along(&self, direction: Vec3) -> f32 {
+        match self.shape {
+            CollisionShape::Cylinder { radius } => radius,
+            CollisionShape::Box { half_width, half_depth } => {
+                half_width * direction.dot(self.axis_x).abs() + half_depth * direction.dot(self.axis_z).abs()
+            }
+        }
+    }
+
+    /// Check if a point lies within the footprint, ignoring its height
+    pub fn contains(&self, point: Vec3) -> bool {
+        let offset = Vec3::new(point.x - self.center.x, 0.0, point.z - self.center.z);
+        match self.shape {
+            CollisionShape::Cylinder { radius } => offset.length_squared() <= radius * radius,
+            CollisionShape::Box { half_width, half_depth } => {
+                offset.dot(self.axis_x).abs() <= half_width && offset.dot(self.axis_z).abs() <= half_depth
+            }
+        }
+    }
+
+    /// How far two footprints overlap, as the horizontal direction from this
+    /// footprint towards the other and the distance the other has to move
+    /// along it to clear this one
+    pub fn penetration(&self, other: &Footprint) -> Option<(Vec3, f32)> {
+        let offset = Vec3::new(other.center.x - self.center.x, 0.0, other.center.z - self.center.z);
+        match (self.shape, other.shape) {
+            (CollisionShape::Cylinder { radius: r1 }, CollisionShape::Cylinder { radius: r2 }) => {
+                let dist = offset.length();
+                let min_dist = r1 + r2;
+                if dist >= min_dist {
+                    return None;
+                }
+                let normal = if dist > 0.001 { offset / dist } else { Vec3::X };
+                Some((normal, min_dist - dist))
+            }
+            (CollisionShape::Box { half_width, half_depth }, CollisionShape::Cylinder { radius }) => {
+                let local_x = offset.dot(self.axis_x);
+                let local_z = offset.dot(self.axis_z);
+                let closest_x = local_x.clamp(-half_width, half_width);
+                let closest_z = local_z.clamp(-half_depth, half_depth);
+                let (normal_x, normal_z, depth) = if closest_x != local_x || closest_z != local_z {
+                    // Circle center outside the box: push along the line
+                    // from the nearest point of the box
+                    let (dx, dz) = (local_x - closest_x, local_z - closest_z);
+                    let dist = dx.hypot(dz);
+                    if dist >= radius {
+                        return None;
+                    }
+                    (dx / dist, dz / dist, radius - dist)
+                } else {
+                    // Circle center inside the box: push out through the
+                    // nearest side
+                    let gap_x = half_width - local_x.abs();
+                    let gap_z = half_depth - local_z.abs();
+                    if gap_x < gap_z {
+                        (1.0_f32.copysign(local_x), 0.0, gap_x + radius)
+                    } else {
+                        (0.0, 1.0_f32.copysign(local_z), gap_z + radius)
+                    }
+                };
+                Some((self.axis_x * normal_x + self.axis_z * normal_z, depth))
+            }
+            (CollisionShape::Cylinder { .. }, CollisionShape::Box { .. }) => {
+                other.penetration(self).map(|(normal, depth)| (-normal, depth))
+            }
+            (CollisionShape::Box { .. }, CollisionShape::Box { .. }) => {
+                // Separating axes: the edges of both boxes
+                let mut best: Option<(Vec3, f32)> = None;
+                for axis in [self.axis_x, self.axis_z, other.axis_x, other.axis_z] {
+                    let distance = offset.dot(axis);
+                    let overlap = self.extent_along(axis) + other.extent_along(axis) - distance.abs();
+                    if overlap <= 0.0 {
+                        return None;
+                    }
+                    if best.is_none_or(|(_, depth)| overlap < depth) {
+                        best = Some((axis * 1.0_f32.copysign(distance), overlap));
+                    }
+                }
+                best
+            }
+        }
+    }
+
+    /// Distance along a ray to where it enters the footprint raised from
+    /// `bottom` to `top`, if it hits it in front of the origin
+    pub fn ray_entry(&self, origin: Vec3, direction: Vec3, bottom: f32, top: f32) -> Option<f32> {
+        let (mut enter, mut exit) = slab(origin.y, direction.y, bottom, top)?;
+        let offset = Vec3::new(origin.x - self.center.x, 0.0, origin.z - self.center.z);
+        let flat = Vec3::new(direction.x, 0.0, direction.z);
+
+        let spans = match self.shape {
+            CollisionShape::Box { half_width, half_depth } => [
+                slab(offset.dot(self.axis_x), flat.dot(self.axis_x), -half_width, half_width)?,
+                slab(offset.dot(self.axis_z), flat.dot(self.axis_z), -half_depth, half_depth)?,
+            ],
+            CollisionShape::Cylinder { radius } => {
+                let a = flat.length_squared();
+                let b = 2.0 * offset.dot(flat);
+                let c = offset.length_squared() - radius * radius;
+                let span = if a < 1e-8 {
+                    if c > 0.0 {
+                        return None;
+                    }
+                    (f32::NEG_INFINITY, f32::INFINITY)
+                } else {
+                    let discriminant = b * b - 4.0 * a * c;
+                    if discriminant < 0.0 {
+                        return None;
+                    }
+                    let root = discriminant.sqrt();
+                    ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a))
+                };
+                [span, span]
+            }
+        };
+        for (span_enter, span_exit) in spans {
+            enter = enter.max(span_enter);
+            exit = exit.min(span_exit);
+        }
+
+        if enter > exit || exit < 0.0 {
+            return None;
+        }
+        Some(enter.max(0.0))
+    }
+}
+
+/// Range of distances along a ray over which one of its coordinates, starting
+/// at `origin` and changing by `direction` per unit, lies between `min` and
+/// `max`
+fn slab(origin: f32, direction: f32, min: f32, max: f32) -> Option<(f32, f32)> {
+    if direction.abs() < 1e-8 {
+        return (min..=max).contains(&origin).then_some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+    let t1 = (min - origin) / direction;
+    let t2 = (max - origin) / direction;
+    Some((t1.min(t2), t1.max(t2)))
+}
+
 /// Physics engine for the desk simulation
 pub struct PhysicsEngine {
     /// Global collision radius multiplier
@@ -29,75 +269,73 @@ pub struct PhysicsEngine {
     pub friction: f32,
     /// Bounce factor for collisions
     pub bounce_factor: f32,
-    /// Gravity constant
+    /// Gravity in units per second squared
     pub gravity: f32,
-    /// Desk bounds (min x, max x, min z, max z)
-    pub desk_bounds: (f32, f32, f32, f32),
-    /// Desk surface Y position
-    pub desk_surface_y: f32,
+    /// Whether sliding objects can go over the desk edge and fall to the floor
+    pub fall_off_desk: bool,
+    /// Desk surface geometry (shape, bounds and height)
+    pub desk: Desk,
 }
 
-impl Default for PhysicsEngine {
-    fn default() -> Self {
-        let config = &CONFIG;
-        let half_width = config.desk.width / 2.0;
-        let half_depth = config.desk.depth / 2.0;
-
-        Self {
+impl PhysicsEngine {
+    /// Create a new physics engine for the configured desk
+    pub fn new(config: &Config) -> Self {
+        let mut engine = Self {
             collision_radius_multiplier: 1.0,
             collision_height_multiplier: 1.0,
-            friction: config.physics.friction,
-            bounce_factor: config.physics.bounce_factor,
-            gravity: config.physics.gravity,
-            desk_bounds: (-half_width, half_width, -half_depth, half_depth),
-            desk_surface_y: config.desk.height,
-        }
+            friction: 0.0,
+            bounce_factor: 0.0,
+            gravity: 0.0,
+            fall_off_desk: false,
+            desk: Desk::from_config(&config.desk),
+        };
+        engine.apply_config(config);
+        engine
     }
-}
 
-impl PhysicsEngine {
-    /// Create a new physics engine
-    pub fn new() -> Self {
-        Self::default()
+    /// Take over changed desk dimensions and physics constants
+    pub fn apply_config(&mut self, config: &Config) {
+        self.friction = config.physics.friction;
+        self.bounce_factor = config.physics.bounce_factor;
+        self.gravity = config.physics.gravity;
+        self.fall_off_desk = config.physics.fall_off_desk;
+        self.desk = Desk::from_config(&config.desk);
     }
 
     /// Get the Y position of the desk surface
     pub fn desk_surface_y(&self) -> f32 {
-        self.desk_surface_y
+        self.desk.surface_y
+    }
+
+    /// Height of the surface under a position: the desk, or the floor
+    /// beside it
+    pub fn surface_y_at(&self, position: Vec3) -> f32 {
+        if self.is_on_desk(position) {
+            self.desk.surface_y
+        } else {
+            FLOOR_Y
+        }
     }
 
     /// Check if a position is within desk bounds
     pub fn is_on_desk(&self, position: Vec3) -> bool {
-        position.x >= self.desk_bounds.0
-            && position.x <= self.desk_bounds.1
-            && position.z >= self.desk_bounds.2
-            && position.z <= self.desk_bounds.3
+        let clamped = self.desk.clamp(position, 0.0);
+        (clamped - position).length_squared() < 1e-6
     }
 
-    /// Clamp a position to desk bounds
+    /// Clamp a position so an object of the given radius stays on the desk
     pub fn clamp_to_desk(&self, position: Vec3, radius: f32) -> Vec3 {
-        Vec3::new(
-            position.x.clamp(self.desk_bounds.0 + radius, self.desk_bounds.1 - radius),
-            position.y,
-            position.z.clamp(self.desk_bounds.2 + radius, self.desk_bounds.3 - radius),
-        )
+        self.desk.clamp(position, radius)
     }
 
-    /// Check collision between two objects
-    pub fn check_collision(&self, obj1: &DeskObject, obj2: &DeskObject) -> bool {
-        if obj1.id == obj2.id {
-            return false;
-        }
-
-        let r1 = obj1.collision_radius() * self.collision_radius_multiplier;
-        let r2 = obj2.collision_radius() * self.collision_radius_multiplier;
-        let min_dist = r1 + r2;
-
-        let dx = obj1.position.x - obj2.position.x;
-        let dz = obj1.position.z - obj2.position.z;
-        let dist_sq = dx * dx + dz * dz;
-
-        dist_sq < min_dist * min_dist
+    /// Distance along a picking ray to where it first hits an object, with
+    /// a small margin around the object so thin ones are easy to grab
+    pub fn ray_hit(&self, object: &DeskObject, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let (bottom, top) = self.vertical_extent(object);
+        object
+            .footprint(self.collision_radius_multiplier)
+            .grown(PICK_MARGIN)
+            .ray_entry(origin, direction, bottom - PICK_MARGIN, top + PICK_MARGIN)
     }
 
     /// Find the best position to place an object (avoiding collisions)
@@ -108,7 +346,8 @@ impl PhysicsEngine {
         other_objects: &[DeskObject],
     ) -> Vec3 {
         let radius = object.collision_radius() * self.collision_radius_multiplier;
-        let mut position = self.clamp_to_desk(target, radius);
+        let mut footprint = object.footprint(self.collision_radius_multiplier);
+        footprint.center = self.clamp_to_desk(target, radius);
 
         // Check for collisions and push away
         for other in other_objects {
@@ -116,25 +355,14 @@ impl PhysicsEngine {
                 continue;
             }
 
-            let other_radius = other.collision_radius() * self.collision_radius_multiplier;
-            let min_dist = radius + other_radius;
-
-            let dx = position.x - other.position.x;
-            let dz = position.z - other.position.z;
-            let dist = (dx * dx + dz * dz).sqrt();
-
-            if dist < min_dist && dist > 0.001 {
-                // Push away from collision
-                let push_dist = min_dist - dist + 0.05;
-                let push_x = (dx / dist) * push_dist;
-                let push_z = (dz / dist) * push_dist;
-                position.x += push_x;
-                position.z += push_z;
+            let other_footprint = other.footprint(self.collision_radius_multiplier);
+            if let Some((normal, depth)) = other_footprint.penetration(&footprint) {
+                footprint.center += normal * (depth + 0.05);
             }
         }
 
         // Re-clamp to desk bounds
-        self.clamp_to_desk(position, radius)
+        self.clamp_to_desk(footprint.center, radius)
     }
 
     /// Calculate the resting Y position for an object (considering stacking)
@@ -143,11 +371,22 @@ impl PhysicsEngine {
         object: &DeskObject,
         other_objects: &[DeskObject],
     ) -> f32 {
-        let radius = object.collision_radius() * self.collision_radius_multiplier;
+        self.resting_support(object, other_objects).0
+    }
+
+    /// Resting Y position for an object and the object it would rest on, if
+    /// any
+    fn resting_support<'a>(
+        &self,
+        object: &DeskObject,
+        other_objects: &'a [DeskObject],
+    ) -> (f32, Option<&'a DeskObject>) {
+        let footprint = object.footprint(self.collision_radius_multiplier);
         let physics = object.object_type.physics();
-        let base_y = self.desk_surface_y + physics.base_offset * object.scale;
+        let base_y = self.surface_y_at(object.position) + physics.base_offset * object.scale;
 
         let mut highest_y = base_y;
+        let mut support = None;
 
         // Check for objects we might be stacking on
         for other in other_objects {
@@ -162,54 +401,99 @@ impl PhysicsEngine {
                 continue;
             }
 
-            let other_radius = other.collision_radius() * self.collision_radius_multiplier;
-            let combined_radius = radius + other_radius;
-
-            let dx = object.position.x - other.position.x;
-            let dz = object.position.z - other.position.z;
-            let dist_sq = dx * dx + dz * dz;
-
             // Check if we're above this object
-            if dist_sq < combined_radius * combined_radius * 0.5 {
+            let other_footprint = other.footprint(self.collision_radius_multiplier);
+            if other_footprint.contains(object.position) || footprint.contains(other.position) {
                 let other_top = other.position.y + other.collision_height() * self.collision_height_multiplier;
                 let stack_y = other_top + physics.base_offset * object.scale;
 
                 if stack_y > highest_y {
                     highest_y = stack_y;
+                    support = Some(other);
                 }
             }
         }
 
-        highest_y
+        (highest_y, support)
+    }
+
+    /// Recompute resting heights for objects loaded from disk or left on a
+    /// resized desk
+    ///
+    /// `target_y` and `original_y` are not persisted, so loaded objects need
+    /// them restored before the first drop update. Objects outside the desk
+    /// are moved back onto it, then settled from the lowest saved height
+    /// upwards so stacks rest on the objects beneath.
+    pub fn settle_objects(&self, objects: &mut [DeskObject]) {
+        let mut order: Vec<usize> = (0..objects.len()).collect();
+        order.sort_by(|&a, &b| objects[a].position.y.total_cmp(&objects[b].position.y));
+
+        let mut settled: Vec<DeskObject> = Vec::with_capacity(objects.len());
+        for index in order {
+            let object = &mut objects[index];
+            let radius = object.collision_radius() * self.collision_radius_multiplier;
+            object.position = self.clamp_to_desk(object.position, radius);
+            let resting_y = self.calculate_resting_y(object, &settled);
+            object.position.y = resting_y;
+            object.target_y = resting_y;
+            object.original_y = resting_y;
+            object.is_dragging = false;
+            object.physics_state = ObjectPhysicsState::default();
+            settled.push(object.clone());
+        }
+    }
+
+    /// Start dragging an object, stopping any motion it had
+    ///
+    /// Toppled objects stay on their side; anything else is held upright.
+    /// Objects picked up from the floor are lifted to above the desk.
+    pub fn start_drag(&self, object: &mut DeskObject) {
+        object.is_dragging = true;
+        let tilt = if object.physics_state.is_toppled() { object.physics_state.tilt } else { Vec3::ZERO };
+        object.physics_state = ObjectPhysicsState { tilt, ..Default::default() };
+        let desk_y = self.desk.surface_y + object.object_type.physics().base_offset * object.scale;
+        object.original_y = object.original_y.max(desk_y);
     }
 
     /// Update object position during dragging
-    pub fn update_dragging(
-        &self,
-        object: &mut DeskObject,
-        target_xz: Vec3,
-        lift_height: f32,
-    ) {
-        let radius = object.collision_radius();
+    pub fn update_dragging(&self, object: &mut DeskObject, target_xz: Vec3) {
+        let radius = object.collision_radius() * self.collision_radius_multiplier;
         let target = self.clamp_to_desk(target_xz, radius);
 
         object.position.x = target.x;
         object.position.z = target.z;
-        object.position.y = object.original_y + lift_height;
         object.is_dragging = true;
     }
 
+    /// Raise a dragged object towards its lift height (smooth animation)
+    ///
+    /// `lift_speed` is the rate per second at which the remaining distance
+    /// closes, and `dt` the step length in seconds.
+    pub fn update_lifting(&self, object: &mut DeskObject, lift_height: f32, lift_speed: f32, dt: f32) -> bool {
+        let lifted_y = object.original_y + lift_height;
+        if object.is_dragging && (object.position.y - lifted_y).abs() > 0.001 {
+            let diff = lifted_y - object.position.y;
+            object.position.y += diff * approach_fraction(lift_speed, dt);
+
+            if (object.position.y - lifted_y).abs() < 0.01 {
+                object.position.y = lifted_y;
+            }
+
+            return true; // Still animating
+        }
+
+        false
+    }
+
     /// Update object position when dropping (smooth animation)
-    pub fn update_dropping(
-        &self,
-        object: &mut DeskObject,
-        other_objects: &[DeskObject],
-        drop_speed: f32,
-    ) -> bool {
+    ///
+    /// `drop_speed` is the rate per second at which the remaining distance
+    /// closes, and `dt` the step length in seconds.
+    pub fn update_dropping(&self, object: &mut DeskObject, drop_speed: f32, dt: f32) -> bool {
         if !object.is_dragging && (object.position.y - object.target_y).abs() > 0.001 {
             // Smoothly move toward target Y
             let diff = object.target_y - object.position.y;
-            object.position.y += diff * drop_speed;
+            object.position.y += diff * approach_fraction(drop_speed, dt);
 
             if (object.position.y - object.target_y).abs() < 0.01 {
                 object.position.y = object.target_y;
@@ -222,11 +506,299 @@ impl PhysicsEngine {
     }
 
     /// End drag operation and calculate final position
-    pub fn end_drag(&self, object: &mut DeskObject, other_objects: &[DeskObject]) {
+    ///
+    /// The object is thrown with the given horizontal velocity and slides on
+    /// from there in the following steps.
+    pub fn end_drag(&self, object: &mut DeskObject, other_objects: &[DeskObject], velocity: Vec3) {
         object.is_dragging = false;
-        object.target_y = self.calculate_resting_y(object, other_objects);
-        object.original_y = object.target_y;
+        let (resting_y, support) = self.resting_support(object, other_objects);
+        object.target_y = resting_y;
+        object.original_y = resting_y;
+        self.tip_if_overhanging(object, support);
+
+        let velocity = Vec3::new(velocity.x, 0.0, velocity.z).clamp_length_max(MAX_RELEASE_SPEED);
+        object.physics_state.velocity = if velocity.length() < REST_SPEED { Vec3::ZERO } else { velocity };
+    }
+
+    /// Stop every object's motion where it is
+    ///
+    /// Wobbling objects stand upright; toppled ones stay on their side.
+    pub fn stop_all(&self, objects: &mut [DeskObject]) {
+        for object in objects {
+            let state = &mut object.physics_state;
+            state.velocity = Vec3::ZERO;
+            state.angular_velocity = 0.0;
+            state.tilt_velocity = Vec3::ZERO;
+            if !state.is_toppled() {
+                state.tilt = Vec3::ZERO;
+            }
+        }
+    }
+
+    /// Stand a toppled object back up
+    pub fn right_object(&self, object: &mut DeskObject) {
+        object.physics_state.tilt = Vec3::ZERO;
+        object.physics_state.tilt_velocity = Vec3::ZERO;
+    }
+
+    /// Advance the simulation by `dt` seconds, returning the ids of objects
+    /// that moved
+    ///
+    /// Released objects slide and spin until friction stops them and bounce
+    /// off each other with the configured bounce factor, heavier objects
+    /// pushing lighter ones further. They stop at the desk edges unless
+    /// falling off is allowed. Unstable objects knocked hard enough wobble or
+    /// topple. Objects whose support moves away settle onto whatever is below
+    /// them.
+    pub fn step(&self, objects: &mut [DeskObject], dt: f32) -> Vec<u64> {
+        let mut moved = Vec::new();
+        if dt <= 0.0 {
+            return moved;
+        }
+
+        for object in objects.iter_mut() {
+            if object.is_dragging || object.physics_state.is_resting() {
+                continue;
+            }
+
+            let physics = object.object_type.physics();
+            let radius = object.collision_radius() * self.collision_radius_multiplier;
+            let deceleration = SLIDE_DECELERATION * self.friction * physics.friction;
+            let state = &mut object.physics_state;
+
+            let speed = state.velocity.length();
+            let slowed = speed - deceleration * dt;
+            state.velocity = if slowed < REST_SPEED { Vec3::ZERO } else { state.velocity * (slowed / speed) };
+
+            let spin = state.angular_velocity.abs();
+            let slowed_spin = spin - deceleration / radius.max(0.1) * dt;
+            state.angular_velocity = if slowed_spin < REST_SPIN {
+                0.0
+            } else {
+                state.angular_velocity.signum() * slowed_spin
+            };
+
+            object.position += state.velocity * dt;
+            object.rotation = Quat::from_rotation_y(state.angular_velocity * dt) * object.rotation;
+            self.step_tilt(object, dt);
+            moved.push(object.id);
+        }
+
+        self.resolve_collisions(objects, &mut moved);
+        if moved.is_empty() {
+            return moved;
+        }
+
+        if !self.fall_off_desk {
+            for object in objects.iter_mut().filter(|o| moved.contains(&o.id)) {
+                self.stop_at_desk_edge(object);
+            }
+        }
+        self.resettle(objects, &mut moved);
+        moved
+    }
+
+    /// Let gravity act on a tilted object: below its tipping angle it rocks
+    /// back upright, beyond it it falls onto its side
+    fn step_tilt(&self, object: &mut DeskObject, dt: f32) {
+        let physics = object.object_type.physics();
+        // A pendulum pivoting on the base edge with its weight at half height
+        let pivot_height = (physics.height * object.scale * object.collision_height_multiplier).max(0.05) * 0.5;
+        let state = &mut object.physics_state;
+        if !physics.can_tip() {
+            state.tilt = Vec3::ZERO;
+            state.tilt_velocity = Vec3::ZERO;
+            return;
+        }
+        if state.tilt_velocity == Vec3::ZERO && (state.tilt == Vec3::ZERO || state.is_toppled()) {
+            return;
+        }
+
+        let angle = state.tilt.length();
+        let axis = if angle > 1e-4 { state.tilt / angle } else { state.tilt_velocity.normalize_or_zero() };
+        if axis == Vec3::ZERO {
+            // A negligible tilt with nothing moving it: stand upright
+            state.tilt = Vec3::ZERO;
+            return;
+        }
+        let tip_angle = physics.stability * MAX_TIP_ANGLE;
+        state.tilt_velocity += axis * (self.gravity / pivot_height * (angle - tip_angle).sin() * dt);
+
+        let previous = state.tilt;
+        state.tilt += state.tilt_velocity * dt;
+        let angle = state.tilt.length();
+        if angle >= TOPPLED_ANGLE {
+            state.tilt *= TOPPLED_ANGLE / angle;
+            state.tilt_velocity = Vec3::ZERO;
+        } else if state.tilt.dot(previous) < 0.0 {
+            // Rocked back through upright onto the opposite edge
+            state.tilt_velocity *= WOBBLE_RESTITUTION;
+        }
+        if angle < REST_TILT && state.tilt_velocity.length() < REST_TILT_SPEED {
+            state.tilt = Vec3::ZERO;
+            state.tilt_velocity = Vec3::ZERO;
+        }
     }
+
+    /// Tip an unstable object in the direction of a sudden change in its
+    /// velocity, more so the taller and less stable it is
+    fn knock(object: &mut DeskObject, velocity_change: Vec3) {
+        let physics = object.object_type.physics();
+        if !physics.can_tip() || object.physics_state.is_toppled() {
+            return;
+        }
+        let height = object.collision_height().max(0.05);
+        let response = TIP_RESPONSE * (1.0 - physics.stability) / height;
+        object.physics_state.tilt_velocity += tilt_towards(velocity_change) * response;
+    }
+
+    /// Start an unstable object tipping over the edge of the object it rests
+    /// on when it sits too far off-center
+    fn tip_if_overhanging(&self, object: &mut DeskObject, support: Option<&DeskObject>) {
+        let Some(support) = support else {
+            return;
+        };
+        let physics = object.object_type.physics();
+        if !physics.can_tip() || object.physics_state.is_toppled() {
+            return;
+        }
+
+        let offset = Vec3::new(object.position.x - support.position.x, 0.0, object.position.z - support.position.z);
+        let distance = offset.length();
+        if distance < 0.001 {
+            return;
+        }
+        let support_extent = support.footprint(self.collision_radius_multiplier).extent_along(offset / distance);
+        let overhang = distance / support_extent.max(0.01);
+        // More stable objects can rest further off-center
+        if overhang <= physics.stability {
+            return;
+        }
+        object.physics_state.tilt_velocity += tilt_towards(offset / distance) * (OVERHANG_TIP_SPEED * overhang);
+    }
+
+    /// Mass used for collisions: heavier and larger objects are harder to push
+    fn mass(object: &DeskObject) -> f32 {
+        object.object_type.physics().weight.max(0.01) * object.scale.powi(3)
+    }
+
+    /// Bottom and top of an object's collision volume, with the top where
+    /// `calculate_resting_y` stacks other objects
+    fn vertical_extent(&self, object: &DeskObject) -> (f32, f32) {
+        let bottom = object.position.y - object.object_type.physics().base_offset * object.scale;
+        let top = object.position.y + object.collision_height() * self.collision_height_multiplier;
+        (bottom, top)
+    }
+
+    /// Push apart overlapping objects where at least one is moving and
+    /// exchange momentum between them
+    fn resolve_collisions(&self, objects: &mut [DeskObject], moved: &mut Vec<u64>) {
+        for i in 0..objects.len() {
+            for j in (i + 1)..objects.len() {
+                let (head, tail) = objects.split_at_mut(j);
+                let (a, b) = (&mut head[i], &mut tail[0]);
+                if a.is_dragging || b.is_dragging {
+                    continue;
+                }
+                if a.physics_state.is_resting() && b.physics_state.is_resting() {
+                    continue;
+                }
+
+                let (a_bottom, a_top) = self.vertical_extent(a);
+                let (b_bottom, b_top) = self.vertical_extent(b);
+                if a_bottom >= b_top - STACK_TOLERANCE || b_bottom >= a_top - STACK_TOLERANCE {
+                    continue;
+                }
+
+                let footprint_a = a.footprint(self.collision_radius_multiplier);
+                let footprint_b = b.footprint(self.collision_radius_multiplier);
+                let Some((normal, overlap)) = footprint_a.penetration(&footprint_b) else {
+                    continue;
+                };
+
+                let inv_a = 1.0 / Self::mass(a);
+                let inv_b = 1.0 / Self::mass(b);
+                let inv_total = inv_a + inv_b;
+
+                // Separate them, moving the lighter object further
+                a.position -= normal * (overlap * inv_a / inv_total);
+                b.position += normal * (overlap * inv_b / inv_total);
+
+                let relative = a.physics_state.velocity - b.physics_state.velocity;
+                let approach = relative.dot(normal);
+                if approach > 0.0 {
+                    let impulse = (1.0 + self.bounce_factor) * approach / inv_total;
+                    a.physics_state.velocity -= normal * (impulse * inv_a);
+                    b.physics_state.velocity += normal * (impulse * inv_b);
+                    Self::knock(a, -normal * (impulse * inv_a));
+                    Self::knock(b, normal * (impulse * inv_b));
+
+                    // Glancing hits set both objects spinning
+                    let tangent = Vec3::new(-normal.z, 0.0, normal.x);
+                    let sideways = relative.dot(tangent) * COLLISION_SPIN / inv_total;
+                    a.physics_state.angular_velocity += sideways * inv_a;
+                    b.physics_state.angular_velocity -= sideways * inv_b;
+                }
+
+                for id in [a.id, b.id] {
+                    if !moved.contains(&id) {
+                        moved.push(id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Keep an object on the desk, stopping its motion towards the edge
+    fn stop_at_desk_edge(&self, object: &mut DeskObject) {
+        let radius = object.collision_radius() * self.collision_radius_multiplier;
+        let clamped = self.clamp_to_desk(object.position, radius);
+        let outward = Vec3::new(object.position.x - clamped.x, 0.0, object.position.z - clamped.z);
+        if outward.length_squared() < 1e-8 {
+            return;
+        }
+
+        let normal = outward.normalize();
+        let state = &mut object.physics_state;
+        let speed_out = state.velocity.dot(normal);
+        if speed_out > 0.0 {
+            state.velocity -= normal * speed_out;
+        }
+        object.position = clamped;
+    }
+
+    /// Recompute resting heights after objects moved, so moved objects rest
+    /// on what is now below them and objects they carried drop down
+    fn resettle(&self, objects: &mut [DeskObject], moved: &mut Vec<u64>) {
+        for i in 0..objects.len() {
+            if objects[i].is_dragging {
+                continue;
+            }
+            let (bottom, _) = self.vertical_extent(&objects[i]);
+            let below: Vec<DeskObject> = objects
+                .iter()
+                .filter(|o| o.id != objects[i].id && !o.is_dragging && self.vertical_extent(o).0 < bottom)
+                .cloned()
+                .collect();
+            let (resting_y, support) = self.resting_support(&objects[i], &below);
+
+            let object = &mut objects[i];
+            if (resting_y - object.target_y).abs() > 0.001 {
+                object.target_y = resting_y;
+                object.original_y = resting_y;
+                self.tip_if_overhanging(object, support);
+                if !moved.contains(&object.id) {
+                    moved.push(object.id);
+                }
+            }
+        }
+    }
+}
+
+/// Fraction of the remaining distance covered in `dt` seconds when closing
+/// it at `rate` per second, independent of how the time is split into steps
+fn approach_fraction(rate: f32, dt: f32) -> f32 {
+    1.0 - (-rate * dt).exp()
 }
 
 /// Ray-plane intersection for mouse picking
@@ -250,3 +822,136 @@ pub fn ray_plane_intersection(
 
     Some(ray_origin + ray_direction * t)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn boxed(x: f32, z: f32, half_width: f32, half_depth: f32, yaw: f32) -> Footprint {
+        Footprint::new(
+            Vec3::new(x, 0.0, z),
+            CollisionShape::Box { half_width, half_depth },
+            Quat::from_rotation_y(yaw),
+        )
+    }
+
+    fn circle(x: f32, z: f32, radius: f32) -> Footprint {
+        Footprint::new(Vec3::new(x, 0.0, z), CollisionShape::Cylinder { radius }, Quat::IDENTITY)
+    }
+
+    fn assert_push(result: Option<(Vec3, f32)>, normal: Vec3, depth: f32) {
+        let (actual_normal, actual_depth) = result.expect("footprints should overlap");
+        assert!(
+            actual_normal.abs_diff_eq(normal, EPSILON),
+            "normal {} != {}",
+            actual_normal,
+            normal
+        );
+        assert!((actual_depth - depth).abs() < EPSILON, "depth {} != {}", actual_depth, depth);
+    }
+
+    #[test]
+    fn circles_overlap_and_separate() {
+        assert_push(circle(0.0, 0.0, 1.0).penetration(&circle(1.5, 0.0, 1.0)), Vec3::X, 0.5);
+        assert!(circle(0.0, 0.0, 1.0).penetration(&circle(2.5, 0.0, 1.0)).is_none());
+        assert!(circle(0.0, 0.0, 1.0).penetration(&circle(2.0, 0.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn boxes_push_out_along_shallowest_axis() {
+        let a = boxed(0.0, 0.0, 1.0, 0.5, 0.0);
+        assert_push(a.penetration(&boxed(1.8, 0.2, 1.0, 0.5, 0.0)), Vec3::X, 0.2);
+        assert_push(a.penetration(&boxed(0.1, -0.8, 1.0, 0.5, 0.0)), Vec3::NEG_Z, 0.2);
+        assert!(a.penetration(&boxed(2.1, 0.0, 1.0, 0.5, 0.0)).is_none());
+        assert!(a.penetration(&boxed(0.0, 1.1, 1.0, 0.5, 0.0)).is_none());
+    }
+
+    #[test]
+    fn rotated_boxes_use_their_own_edges() {
+        // A diamond next to a square: the corner reaches sqrt(2) out
+        let square = boxed(0.0, 0.0, 1.0, 1.0, 0.0);
+        let diamond_near = boxed(2.3, 0.0, 1.0, 1.0, FRAC_PI_4);
+        let diamond_far = boxed(2.5, 0.0, 1.0, 1.0, FRAC_PI_4);
+        assert_push(square.penetration(&diamond_near), Vec3::X, 2.0_f32.sqrt() + 1.0 - 2.3);
+        assert!(square.penetration(&diamond_far).is_none());
+
+        // Two long thin boxes side by side overlap only when turned to match
+        let plank = boxed(0.0, 0.0, 2.0, 0.2, 0.0);
+        assert!(plank.penetration(&boxed(0.0, 0.6, 2.0, 0.2, 0.0)).is_none());
+        assert!(plank.penetration(&boxed(0.0, 0.6, 2.0, 0.2, 0.5)).is_some());
+    }
+
+    #[test]
+    fn box_and_circle_overlap_either_way_round() {
+        let table = boxed(0.0, 0.0, 1.0, 0.5, 0.0);
+        let mug = circle(1.3, 0.0, 0.5);
+        assert_push(table.penetration(&mug), Vec3::X, 0.2);
+        assert_push(mug.penetration(&table), Vec3::NEG_X, 0.2);
+
+        // Near a corner the push points away from the corner
+        assert_push(table.penetration(&circle(1.24, 0.82, 0.5)), Vec3::new(0.6, 0.0, 0.8), 0.1);
+        assert!(table.penetration(&circle(1.5, 0.9, 0.5)).is_none());
+        assert!(table.penetration(&circle(1.6, 0.0, 0.5)).is_none());
+    }
+
+    #[test]
+    fn circle_inside_box_leaves_through_nearest_side() {
+        let table = boxed(0.0, 0.0, 2.0, 1.0, 0.0);
+        assert_push(table.penetration(&circle(0.2, 0.7, 0.1)), Vec3::Z, 0.4);
+        assert_push(table.penetration(&circle(-1.9, 0.0, 0.1)), Vec3::NEG_X, 0.2);
+    }
+
+    #[test]
+    fn box_and_circle_follow_box_rotation() {
+        // Turned a quarter, the box's long side runs along Z
+        let table = boxed(0.0, 0.0, 1.0, 0.25, FRAC_PI_2);
+        assert!(table.penetration(&circle(0.0, 0.9, 0.2)).is_some());
+        assert!(table.penetration(&circle(0.9, 0.0, 0.2)).is_none());
+    }
+
+    #[test]
+    fn ray_enters_box_from_above() {
+        let book = boxed(0.0, 0.0, 1.0, 0.5, 0.0);
+        let down = Vec3::NEG_Y;
+        let hit = book.ray_entry(Vec3::new(0.5, 5.0, 0.2), down, 0.0, 1.0).unwrap();
+        assert!((hit - 4.0).abs() < EPSILON);
+        assert!(book.ray_entry(Vec3::new(1.5, 5.0, 0.0), down, 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn ray_enters_box_from_the_side() {
+        let book = boxed(0.0, 0.0, 1.0, 0.5, 0.0);
+        let hit = book.ray_entry(Vec3::new(-4.0, 0.5, 0.0), Vec3::X, 0.0, 1.0).unwrap();
+        assert!((hit - 3.0).abs() < EPSILON);
+        // Passes above the top, or points away
+        assert!(book.ray_entry(Vec3::new(-4.0, 1.5, 0.0), Vec3::X, 0.0, 1.0).is_none());
+        assert!(book.ray_entry(Vec3::new(-4.0, 0.5, 0.0), Vec3::NEG_X, 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn ray_respects_box_rotation() {
+        let turned = boxed(0.0, 0.0, 1.0, 0.1, FRAC_PI_2);
+        let down = Vec3::NEG_Y;
+        assert!(turned.ray_entry(Vec3::new(0.0, 5.0, 0.9), down, 0.0, 1.0).is_some());
+        assert!(turned.ray_entry(Vec3::new(0.9, 5.0, 0.0), down, 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn ray_enters_cylinder() {
+        let mug = circle(0.0, 0.0, 0.5);
+        let hit = mug.ray_entry(Vec3::new(-3.0, 0.5, 0.0), Vec3::X, 0.0, 1.0).unwrap();
+        assert!((hit - 2.5).abs() < EPSILON);
+        assert!(mug.ray_entry(Vec3::new(-3.0, 0.5, 0.6), Vec3::X, 0.0, 1.0).is_none());
+        assert!(mug.ray_entry(Vec3::new(0.3, 5.0, 0.3), Vec3::NEG_Y, 0.0, 1.0).is_some());
+        assert!(mug.ray_entry(Vec3::new(0.4, 5.0, 0.4), Vec3::NEG_Y, 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn ray_starting_inside_hits_at_zero() {
+        let mug = circle(0.0, 0.0, 0.5);
+        assert_eq!(mug.ray_entry(Vec3::new(0.0, 0.5, 0.0), Vec3::X, 0.0, 1.0), Some(0.0));
+    }
+}
@@ -0,0 +1,291 @@
+//! Pixelation module
+//!
+//! Renders the scene at a fraction of the window resolution and scales it up
+//! with hard pixel edges, outlining objects where depth jumps and
+//! highlighting folds where surface normals change (Signalis-style).
+
+use crate::camera::Camera;
+use crate::config::PixelationConfig;
+use wgpu::util::DeviceExt;
+
+/// Format of the scene normal target
+pub const NORMAL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Format of the scene depth target
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Pixelation uniform buffer data
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct PixelationUniform {
+    /// Pixel size, normal edge strength, depth edge strength (w unused)
+    params: [f32; 4],
+    /// Near plane, far plane, 1 for an orthographic projection (w unused)
+    depth_params: [f32; 4],
+}
+
+/// Textures the scene is rendered into before being pixelated
+struct SceneTargets {
+    color: wgpu::TextureView,
+    normal: wgpu::TextureView,
+    depth: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    /// Size of the targets in scene pixels
+    size: (u32, u32),
+}
+
+/// Offscreen scene targets and the pass that draws them to the window
+pub struct PixelationPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    color_format: wgpu::TextureFormat,
+    targets: SceneTargets,
+    /// Pixel size in window pixels, 1 when pixelation is disabled
+    pixel_size: u32,
+}
+
+impl PixelationPass {
+    /// Create the pass for a window of the given size, rendering the scene in
+    /// `color_format`
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        window_size: (u32, u32),
+        settings: &PixelationConfig,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Pixelation Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("pixelation.wgsl").into()),
+        });
+
+        let texture_entry = |binding: u32, sample_type: wgpu::TextureSampleType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                texture_entry(0, wgpu::TextureSampleType::Float { filterable: false }),
+                texture_entry(1, wgpu::TextureSampleType::Float { filterable: false }),
+                texture_entry(2, wgpu::TextureSampleType::Depth),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("pixelation_bind_group_layout"),
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pixelation Buffer"),
+            contents: bytemuck::cast_slice(&[PixelationUniform {
+                params: [1.0, 0.0, 0.0, 0.0],
+                depth_params: [0.0; 4],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pixelation Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pixelation Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let pixel_size = effective_pixel_size(settings);
+        let targets = Self::create_targets(
+            device,
+            &bind_group_layout,
+            &uniform_buffer,
+            color_format,
+            scene_size(window_size, pixel_size),
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            color_format,
+            targets,
+            pixel_size,
+        }
+    }
+
+    /// Match the scene targets to the window size and pixel size, recreating
+    /// them only when the scene resolution changes
+    pub fn resize(&mut self, device: &wgpu::Device, window_size: (u32, u32), settings: &PixelationConfig) {
+        self.pixel_size = effective_pixel_size(settings);
+        let size = scene_size(window_size, self.pixel_size);
+        if size != self.targets.size {
+            self.targets = Self::create_targets(
+                device,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                self.color_format,
+                size,
+            );
+        }
+    }
+
+    /// Upload the edge settings and the camera's depth range for this frame
+    pub fn update(&self, queue: &wgpu::Queue, settings: &PixelationConfig, camera: &Camera) {
+        let (near, far, orthographic) = camera.depth_range();
+        let (normal_edges, depth_edges) = if settings.enabled {
+            (settings.normal_edge_strength, settings.depth_edge_strength)
+        } else {
+            (0.0, 0.0)
+        };
+        let uniform = PixelationUniform {
+            params: [self.pixel_size as f32, normal_edges, depth_edges, 0.0],
+            depth_params: [near, far, if orthographic { 1.0 } else { 0.0 }, 0.0],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    /// Color target the scene is rendered into
+    pub fn color_view(&self) -> &wgpu::TextureView {
+        &self.targets.color
+    }
+
+    /// Normal target the scene is rendered into
+    pub fn normal_view(&self) -> &wgpu::TextureView {
+        &self.targets.normal
+    }
+
+    /// Depth target the scene is rendered into
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.targets.depth
+    }
+
+    /// Draw the rendered scene into the window, pixelated
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Pixelation Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.targets.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn create_targets(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        color_format: wgpu::TextureFormat,
+        size: (u32, u32),
+    ) -> SceneTargets {
+        let create = |label: &str, format: wgpu::TextureFormat| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: size.0,
+                        height: size.1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        let color = create("Scene Color Texture", color_format);
+        let normal = create("Scene Normal Texture", NORMAL_FORMAT);
+        let depth = create("Depth Texture", DEPTH_FORMAT);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&color),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&normal),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&depth),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("pixelation_bind_group"),
+        });
+
+        SceneTargets {
+            color,
+            normal,
+            depth,
+            bind_group,
+            size,
+        }
+    }
+}
+
+/// Window pixels per scene pixel
+fn effective_pixel_size(settings: &PixelationConfig) -> u32 {
+    if settings.enabled {
+        settings.pixel_size.max(1)
+    } else {
+        1
+    }
+}
+
+/// Scene resolution for a window, rounding up so the scene covers it
+fn scene_size((width, height): (u32, u32), pixel_size: u32) -> (u32, u32) {
+    (width.div_ceil(pixel_size).max(1), height.div_ceil(pixel_size).max(1))
+}
@@ -0,0 +1,99 @@
+// Focus Desktop Simulator - Pixelation Shader
+// Upscales the low-resolution scene with hard pixel edges and draws outlines
+// where depth jumps and highlights where surfaces fold
+
+struct PixelationUniform {
+    // x = pixel size, y = normal edge strength, z = depth edge strength
+    params: vec4<f32>,
+    // x = near plane, y = far plane, z = 1 for an orthographic projection
+    depth_params: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var scene_color: texture_2d<f32>;
+
+@group(0) @binding(1)
+var scene_normal: texture_2d<f32>;
+
+@group(0) @binding(2)
+var scene_depth: texture_depth_2d;
+
+@group(0) @binding(3)
+var<uniform> pixelation: PixelationUniform;
+
+// Depth difference to a neighbouring pixel that counts as an outline, in
+// world units, growing with distance
+const DEPTH_EDGE_THRESHOLD: f32 = 0.05;
+const DEPTH_EDGE_FALLOFF: f32 = 0.02;
+
+// Normal difference to a neighbouring pixel that counts as a fold
+const NORMAL_EDGE_THRESHOLD: f32 = 0.3;
+
+// Fullscreen triangle
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn clamp_texel(texel: vec2<i32>) -> vec2<i32> {
+    let size = vec2<i32>(textureDimensions(scene_depth));
+    return clamp(texel, vec2<i32>(0, 0), size - vec2<i32>(1, 1));
+}
+
+// Distance from the camera plane of the surface seen at a texel
+fn linear_depth(texel: vec2<i32>) -> f32 {
+    let depth = textureLoad(scene_depth, clamp_texel(texel), 0);
+    let near = pixelation.depth_params.x;
+    let far = pixelation.depth_params.y;
+    if (pixelation.depth_params.z > 0.5) {
+        return near + depth * (far - near);
+    }
+    return near * far / (far - depth * (far - near));
+}
+
+// Surface normal at a texel, zero over the background
+fn surface_normal(texel: vec2<i32>) -> vec3<f32> {
+    let encoded = textureLoad(scene_normal, clamp_texel(texel), 0);
+    return (encoded.xyz * 2.0 - 1.0) * encoded.a;
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>(floor(position.xy / pixelation.params.x));
+    let color = textureLoad(scene_color, clamp_texel(texel), 0).rgb;
+
+    let depth = linear_depth(texel);
+    let normal = surface_normal(texel);
+    let threshold = DEPTH_EDGE_THRESHOLD + DEPTH_EDGE_FALLOFF * abs(depth);
+
+    var depth_edge = 0.0;
+    var normal_edge = 0.0;
+    var offsets = array<vec2<i32>, 4>(
+        vec2<i32>(1, 0),
+        vec2<i32>(-1, 0),
+        vec2<i32>(0, 1),
+        vec2<i32>(0, -1),
+    );
+    for (var i = 0; i < 4; i++) {
+        let neighbour = texel + offsets[i];
+        let depth_difference = linear_depth(neighbour) - depth;
+        // Outline only the nearer side of a depth jump
+        if (depth_difference > threshold) {
+            depth_edge = 1.0;
+        } else if (depth_difference > -threshold) {
+            let normal_difference = 1.0 - dot(normal, surface_normal(neighbour));
+            if (normal_difference > NORMAL_EDGE_THRESHOLD) {
+                normal_edge = 1.0;
+            }
+        }
+    }
+
+    var result = color;
+    if (depth_edge > 0.0) {
+        result = result * (1.0 - 0.5 * pixelation.params.z);
+    } else if (normal_edge > 0.0) {
+        result = result * (1.0 + 0.5 * pixelation.params.y);
+    }
+    return vec4<f32>(result, 1.0);
+}
@@ -7,6 +7,18 @@ struct CameraUniform {
     position: vec4<f32>,
 }
 
+// Lighting and fog uniform buffer, fed from the config
+struct LightingUniform {
+    // Direction towards the directional light (w unused)
+    light_direction: vec4<f32>,
+    ambient_color: vec4<f32>,
+    directional_color: vec4<f32>,
+    // Matches the clear color so distant geometry fades into the background
+    fog_color: vec4<f32>,
+    // x = start distance, y = fade range, z = maximum fog blend
+    fog_params: vec4<f32>,
+}
+
 // Model uniform buffer for per-object transforms
 struct ModelUniform {
     model: mat4x4<f32>,
@@ -15,6 +27,9 @@ struct ModelUniform {
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
+@group(0) @binding(1)
+var<uniform> lighting: LightingUniform;
+
 @group(1) @binding(0)
 var<uniform> model: ModelUniform;
 
@@ -60,33 +75,38 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     return out;
 }
 
+// Fragment output: the lit color, and the surface normal for the
+// pixelation pass's edge detection (alpha marks covered pixels)
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+}
+
 // Fragment shader with basic lighting
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Light direction (from top-right)
-    let light_dir = normalize(vec3<f32>(0.5, 1.0, 0.3));
-
-    // Ambient light
-    let ambient_color = vec3<f32>(0.25, 0.25, 0.35);
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    // Light direction
+    let light_dir = normalize(lighting.light_direction.xyz);
 
     // Directional light
     let normal = normalize(in.world_normal);
     let diffuse = max(dot(normal, light_dir), 0.0);
 
     // Combine lighting
-    let light = ambient_color + diffuse * vec3<f32>(0.8, 0.8, 0.75);
+    let light = lighting.ambient_color.rgb + diffuse * lighting.directional_color.rgb;
 
     // Apply lighting to base color
     let lit_color = in.color.rgb * light;
 
     // Simple fog effect based on distance from camera
     let dist = length(in.world_position - camera.position.xyz);
-    let fog_factor = 1.0 - clamp((dist - 10.0) / 40.0, 0.0, 0.6);
+    let fog = lighting.fog_params;
+    let fog_factor = 1.0 - clamp((dist - fog.x) / fog.y, 0.0, fog.z);
 
-    // Background/fog color
-    let fog_color = vec3<f32>(0.1, 0.1, 0.18);
+    let final_color = mix(lighting.fog_color.rgb, lit_color, fog_factor);
 
-    let final_color = mix(fog_color, lit_color, fog_factor);
-
-    return vec4<f32>(final_color, in.color.a);
+    var out: FragmentOutput;
+    out.color = vec4<f32>(final_color, in.color.a);
+    out.normal = vec4<f32>(normal * 0.5 + 0.5, 1.0);
+    return out;
 }
@@ -2,10 +2,35 @@
 //!
 //! Handles saving and loading application state to/from disk.
 
-use crate::desk_object::DeskObject;
+use crate::camera::{CameraMode, CameraView};
+use crate::desk_object::{vec3_serde, DeskObject, MAX_SCALE, MIN_SCALE};
+use glam::{Quat, Vec3};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Current version of the state file format
+pub const CURRENT_STATE_VERSION: u32 = 2;
+
+/// Number of previous state file generations kept for recovery
+const BACKUP_GENERATIONS: u32 = 3;
+
+/// Quiet period after the last change before an autosave starts
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// How often the state file is checked for external changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A single migration step operating on the raw JSON of a state file
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Migration chain: `MIGRATIONS[i]` upgrades a file from version `i + 1` to `i + 2`
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
 
 /// Application state that gets persisted
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,16 +45,88 @@ pub struct AppState {
     pub collision_height_multiplier: f32,
     /// Next object ID to use
     pub next_object_id: u64,
+    /// Camera, panel and window layout from the last session
+    #[serde(default)]
+    pub view: Option<ViewState>,
+    /// Time of the last unsaved change (`None` when the state is clean)
+    #[serde(skip)]
+    modified_at: Option<Instant>,
+    /// File this state is loaded from and saved to
+    #[serde(skip)]
+    file_path: Option<PathBuf>,
+    /// Format version of the loaded file if a newer build wrote it; the
+    /// original is copied aside before it is first overwritten
+    #[serde(skip)]
+    newer_version: Option<u32>,
+}
+
+/// Camera pose saved with the desk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraPose {
+    /// Camera position in world space
+    #[serde(with = "vec3_serde")]
+    pub position: Vec3,
+    /// Horizontal rotation in radians
+    pub yaw: f32,
+    /// Vertical rotation in radians
+    pub pitch: f32,
+    /// Field of view in degrees
+    pub fov: f32,
+    /// Active camera mode; the pose above is always the perspective one
+    #[serde(default)]
+    pub mode: CameraMode,
+}
+
+/// Window placement saved with the desk (physical pixels)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub width: u32,
+    pub height: u32,
+    /// Outer position, if the platform reported one
+    #[serde(default)]
+    pub position: Option<(i32, i32)>,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+/// Camera pose and UI layout restored on startup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewState {
+    #[serde(default)]
+    pub camera: Option<CameraPose>,
+    /// Whether the palette sidebar was open
+    #[serde(default)]
+    pub left_sidebar_open: bool,
+    /// Names of the expanded palette categories
+    #[serde(default)]
+    pub expanded_categories: Vec<String>,
+    #[serde(default)]
+    pub window: Option<WindowGeometry>,
+    /// Saved camera views, in the order they are cycled through
+    #[serde(default)]
+    pub bookmarks: Vec<ViewBookmark>,
+}
+
+/// A named camera view the user can jump back to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewBookmark {
+    pub name: String,
+    #[serde(flatten)]
+    pub view: CameraView,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: CURRENT_STATE_VERSION,
             objects: Vec::new(),
             collision_radius_multiplier: 1.0,
             collision_height_multiplier: 1.0,
             next_object_id: 1,
+            view: None,
+            modified_at: None,
+            file_path: None,
+            newer_version: None,
         }
     }
 }
@@ -40,59 +137,203 @@ impl AppState {
         Self::default()
     }
 
-    /// Get the path to the state file
-    fn state_file_path() -> Option<PathBuf> {
-        dirs::data_dir().map(|mut path| {
-            path.push("focus-desktop-simulator");
-            fs::create_dir_all(&path).ok();
-            path.push("desk-state.json");
-            path
-        })
+    /// Create an empty state that will be saved to the given file
+    pub fn empty_at(path: PathBuf) -> Self {
+        Self {
+            file_path: Some(path),
+            ..Self::default()
+        }
     }
 
-    /// Load state from disk
-    pub fn load() -> Self {
-        let path = match Self::state_file_path() {
-            Some(p) => p,
-            None => {
-                log::warn!("Could not determine data directory, using default state");
-                return Self::default();
-            }
-        };
+    /// Load state from the given file
+    ///
+    /// Falls back to the most recent readable backup generation when the main
+    /// state file is missing or cannot be recovered.
+    pub fn load_from(path: PathBuf) -> Self {
+        let mut state = Self::load_or_recover(&path, true);
+        state.file_path = Some(path);
+        state
+    }
 
-        if !path.exists() {
-            log::info!("No saved state found, using default");
-            return Self::default();
-        }
+    /// Load state from the given file like `load_from`, but without writing
+    /// anything, for commands that only read the desk
+    ///
+    /// A corrupted state file is not copied to `.json.backup`, so an earlier
+    /// copy there is kept.
+    pub fn read_from(path: PathBuf) -> Self {
+        let mut state = Self::load_or_recover(&path, false);
+        state.file_path = Some(path);
+        state
+    }
 
-        match fs::read_to_string(&path) {
-            Ok(content) => {
-                match serde_json::from_str::<AppState>(&content) {
-                    Ok(state) => {
-                        log::info!("Loaded state with {} objects", state.objects.len());
-                        state
-                    }
-                    Err(e) => {
-                        log::warn!(
-                            "State file format is outdated or corrupted: {}. \
-                            Creating backup and using default state.",
-                            e
-                        );
-                        // Try to backup the corrupted file for potential recovery
-                        Self::backup_corrupted_state(&path);
-                        Self::default()
+    fn load_or_recover(path: &Path, back_up_corrupted: bool) -> Self {
+        if path.exists() {
+            match Self::load_file(path) {
+                Ok(state) => {
+                    log::info!("Loaded state with {} objects", state.objects.len());
+                    return state;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "State file could not be migrated or is corrupted: {}. \
+                        Trying previous generations.",
+                        e
+                    );
+                    // Try to backup the corrupted file for potential recovery
+                    if back_up_corrupted {
+                        Self::backup_corrupted_state(path);
                     }
                 }
             }
-            Err(e) => {
-                log::error!("Failed to read state file: {}", e);
-                Self::default()
+        }
+
+        for generation in 1..=BACKUP_GENERATIONS {
+            let backup_path = generation_path(path, generation);
+            if !backup_path.exists() {
+                continue;
+            }
+            match Self::load_file(&backup_path) {
+                Ok(state) => {
+                    log::warn!(
+                        "Recovered state with {} objects from backup {:?}",
+                        state.objects.len(),
+                        backup_path
+                    );
+                    return state;
+                }
+                Err(e) => log::warn!("Backup {:?} is unusable: {}", backup_path, e),
+            }
+        }
+
+        log::info!("No usable saved state found, using default");
+        Self::default()
+    }
+
+    /// Read and parse a single state file
+    fn load_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("read failed: {}", e))?;
+        let mut state = Self::parse_and_migrate(&content)?;
+
+        let repairs = state.repair();
+        if !repairs.is_empty() {
+            log::warn!("Repaired {} problems in {:?}:", repairs.len(), path);
+            for repair in &repairs {
+                log::warn!("  {}", repair);
+            }
+            // Write the repaired state back on the next autosave
+            state.mark_dirty();
+        }
+        Ok(state)
+    }
+
+    /// Check a state file without changing it, returning the parsed state
+    /// and the repairs loading it would make
+    ///
+    /// Fails if the file cannot be read, parsed or migrated. Backups are not
+    /// consulted.
+    pub fn check_file(path: &Path) -> Result<(Self, Vec<String>), String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("read failed: {}", e))?;
+        let mut state = Self::parse_and_migrate(&content)?;
+        let mut repairs = state.repair();
+        if let Some(version) = state.newer_version {
+            repairs.push(format!(
+                "written by a newer version ({}); settings this version does not know would be dropped",
+                version
+            ));
+        }
+        Ok((state, repairs))
+    }
+
+    /// Fix object data that would break lookups or rendering, returning a
+    /// description of every change made
+    ///
+    /// Duplicate and zero IDs are reassigned, `next_object_id` is moved past
+    /// the highest ID, non-finite coordinates are reset, rotations are
+    /// normalized and scales are clamped. Moving objects back onto the desk
+    /// is left to `PhysicsEngine::settle_objects`, which knows its size.
+    pub fn repair(&mut self) -> Vec<String> {
+        let mut repairs = Vec::new();
+
+        let max_id = self.objects.iter().map(|o| o.id).max().unwrap_or(0);
+        let mut next_free = max_id + 1;
+        let mut seen = HashSet::new();
+        for obj in &mut self.objects {
+            if obj.id == 0 || !seen.insert(obj.id) {
+                repairs.push(format!(
+                    "{} had duplicate or invalid ID {}, reassigned to {}",
+                    obj.object_type.display_name(),
+                    obj.id,
+                    next_free
+                ));
+                obj.id = next_free;
+                seen.insert(next_free);
+                next_free += 1;
+            }
+        }
+        if self.next_object_id < next_free {
+            repairs.push(format!(
+                "next_object_id {} was not above the highest ID, set to {}",
+                self.next_object_id, next_free
+            ));
+            self.next_object_id = next_free;
+        }
+
+        for obj in &mut self.objects {
+            let name = obj.object_type.display_name();
+            let id = obj.id;
+
+            let position = obj.position;
+            let x = finite_or(position.x, 0.0);
+            let y = finite_or(position.y, 0.0).max(0.0);
+            let z = finite_or(position.z, 0.0);
+            obj.position = glam::Vec3::new(x, y, z);
+            if obj.position != position {
+                repairs.push(format!(
+                    "{} {} had invalid position {}, moved to {}",
+                    name, id, position, obj.position
+                ));
+            }
+
+            let rotation = obj.rotation;
+            if !rotation.is_finite() || rotation.length_squared() < 1e-6 {
+                obj.rotation = Quat::IDENTITY;
+                repairs.push(format!("{} {} had invalid rotation, reset", name, id));
+            } else if !rotation.is_normalized() {
+                obj.rotation = rotation.normalize();
+                repairs.push(format!("{} {} had unnormalized rotation, normalized", name, id));
+            }
+
+            let scale = obj.scale;
+            obj.scale = finite_or(scale, 1.0).clamp(MIN_SCALE, MAX_SCALE);
+            if obj.scale != scale {
+                repairs.push(format!(
+                    "{} {} had scale {}, clamped to {}",
+                    name, id, scale, obj.scale
+                ));
             }
         }
+
+        repairs
+    }
+
+    /// Parse raw state file content, running any migrations needed to reach
+    /// the current format version
+    fn parse_and_migrate(content: &str) -> Result<Self, String> {
+        let mut value: Value =
+            serde_json::from_str(content).map_err(|e| format!("invalid JSON: {}", e))?;
+        migrate_value(&mut value)?;
+        let mut state: Self =
+            serde_json::from_value(value).map_err(|e| format!("invalid state: {}", e))?;
+        if state.version > CURRENT_STATE_VERSION {
+            // Whatever is saved from here on is in this build's format
+            state.newer_version = Some(state.version);
+            state.version = CURRENT_STATE_VERSION;
+        }
+        Ok(state)
     }
 
     /// Backup a corrupted state file so user doesn't lose data
-    fn backup_corrupted_state(path: &PathBuf) {
+    fn backup_corrupted_state(path: &Path) {
         let backup_path = path.with_extension("json.backup");
         if let Err(e) = fs::copy(path, &backup_path) {
             log::warn!("Could not backup corrupted state file: {}", e);
@@ -106,17 +347,121 @@ impl AppState {
     }
 
     /// Save state to disk
+    ///
+    /// The new content is written to a temporary file and synced before it is
+    /// renamed over the state file, so a crash mid-save never leaves a
+    /// half-written or missing state behind. The previous file is kept as
+    /// generation 1.
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let path = Self::state_file_path()
-            .ok_or("Could not determine data directory")?;
+        let path = self
+            .file_path
+            .as_deref()
+            .ok_or("State has no file to save to")?;
 
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(&path, content)?;
+
+        if let Some(version) = self.newer_version {
+            Self::preserve_newer_file(path, version)?;
+        }
+
+        let tmp_path = path.with_extension("json.tmp");
+        write_synced(&tmp_path, content.as_bytes())?;
+
+        Self::rotate_backups(path);
+        fs::rename(&tmp_path, path)?;
+        sync_parent_dir(path);
 
         log::info!("Saved state with {} objects to {:?}", self.objects.len(), path);
         Ok(())
     }
 
+    /// Copy a state file written by a newer build to `<name>.v<version>.json`
+    /// before it is first overwritten, so the fields this build drops are
+    /// not lost
+    ///
+    /// An existing copy is the original and is never replaced.
+    fn preserve_newer_file(path: &Path, version: u32) -> std::io::Result<()> {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let copy_path = path.with_file_name(format!("{}.v{}.json", stem, version));
+        if copy_path.exists() || !path.exists() {
+            return Ok(());
+        }
+        fs::copy(path, &copy_path)?;
+        log::warn!(
+            "State file was written by a newer version ({}); kept the original as {:?}",
+            version,
+            copy_path
+        );
+        Ok(())
+    }
+
+    /// Write this state next to its file as `<name>.conflict.json`, keeping
+    /// in-app changes that could not be reconciled with an external edit
+    pub fn save_conflict_copy(&self) -> Result<PathBuf, String> {
+        let path = self.file_path.as_deref().ok_or("State has no file to save to")?;
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let conflict_path = path.with_file_name(format!("{}.conflict.json", stem));
+
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_atomic(&conflict_path, content.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(conflict_path)
+    }
+
+    /// File this state is saved to
+    pub fn file_path(&self) -> Option<&Path> {
+        self.file_path.as_deref()
+    }
+
+    /// Point this state at a different file, e.g. when duplicating a layout
+    pub fn set_file_path(&mut self, path: PathBuf) {
+        self.file_path = Some(path);
+    }
+
+    /// Delete a state file together with all of its backup generations
+    pub fn remove_files(path: &Path) -> std::io::Result<()> {
+        for generation in 1..=BACKUP_GENERATIONS {
+            let backup_path = generation_path(path, generation);
+            if backup_path.exists() {
+                fs::remove_file(backup_path)?;
+            }
+        }
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Shift backup generations up by one, keeping the current state file as
+    /// generation 1 and dropping the oldest
+    ///
+    /// The current file stays in place (generation 1 is a hard link to it, or
+    /// a copy where links are not supported) so that it is only ever replaced
+    /// by the rename of the new content.
+    fn rotate_backups(path: &Path) {
+        for generation in (1..BACKUP_GENERATIONS).rev() {
+            let from = generation_path(path, generation);
+            if from.exists() {
+                if let Err(e) = fs::rename(&from, generation_path(path, generation + 1)) {
+                    log::warn!("Could not rotate backup {:?}: {}", from, e);
+                }
+            }
+        }
+
+        if path.exists() {
+            let backup_path = generation_path(path, 1);
+            let kept = fs::hard_link(path, &backup_path).or_else(|_| fs::copy(path, &backup_path).map(|_| ()));
+            if let Err(e) = kept {
+                log::warn!("Could not keep previous state as backup: {}", e);
+            }
+        }
+    }
+
     /// Generate a new unique object ID
     pub fn next_id(&mut self) -> u64 {
         let id = self.next_object_id;
@@ -127,17 +472,32 @@ impl AppState {
     /// Add an object to the state
     pub fn add_object(&mut self, object: DeskObject) {
         self.objects.push(object);
+        self.mark_dirty();
     }
 
     /// Remove an object by ID
     pub fn remove_object(&mut self, id: u64) -> Option<DeskObject> {
         if let Some(pos) = self.objects.iter().position(|o| o.id == id) {
+            self.mark_dirty();
             Some(self.objects.remove(pos))
         } else {
             None
         }
     }
 
+    /// Insert an object at a list position (clamped to the list length)
+    pub fn insert_object(&mut self, index: usize, object: DeskObject) {
+        let index = index.min(self.objects.len());
+        self.objects.insert(index, object);
+        self.mark_dirty();
+    }
+
+    /// Replace the whole object list
+    pub fn replace_objects(&mut self, objects: Vec<DeskObject>) {
+        self.objects = objects;
+        self.mark_dirty();
+    }
+
     /// Get an object by ID
     pub fn get_object(&self, id: u64) -> Option<&DeskObject> {
         self.objects.iter().find(|o| o.id == id)
@@ -151,5 +511,489 @@ impl AppState {
     /// Clear all objects
     pub fn clear_objects(&mut self) {
         self.objects.clear();
+        self.mark_dirty();
+    }
+
+    /// Record that the state has changed and needs saving
+    ///
+    /// Callers that mutate objects through `get_object_mut` must call this
+    /// themselves once the edit is complete.
+    pub fn mark_dirty(&mut self) {
+        self.modified_at = Some(Instant::now());
+    }
+
+    /// Whether there are changes that have not been saved yet
+    pub fn is_dirty(&self) -> bool {
+        self.modified_at.is_some()
+    }
+
+    /// Mark the state as saved
+    fn mark_clean(&mut self) {
+        self.modified_at = None;
+    }
+}
+
+/// Debounced background autosave and external change detection
+///
+/// Once the state has been dirty and untouched for `AUTOSAVE_DELAY`, a
+/// snapshot is written on a worker thread so the render loop never blocks on
+/// disk I/O. Only one save runs at a time.
+///
+/// The saver also remembers the modification time of the last file it wrote
+/// or loaded, so edits made to the state file by other programs can be told
+/// apart from its own saves.
+pub struct AutoSaver {
+    /// Save currently running in the background
+    pending: Option<JoinHandle<Result<Option<SystemTime>, String>>>,
+    /// Modification time of the state file as last written or loaded by us
+    known_mtime: Option<SystemTime>,
+    /// When the state file was last checked for external changes
+    last_check: Instant,
+    /// Autosave is paused while an external change awaits a decision
+    suspended: bool,
+}
+
+impl Default for AutoSaver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoSaver {
+    pub fn new() -> Self {
+        Self {
+            pending: None,
+            known_mtime: None,
+            last_check: Instant::now(),
+            suspended: false,
+        }
+    }
+
+    /// Check on the running save and start a new one if the state has settled
+    pub fn poll(&mut self, state: &mut AppState) {
+        if let Some(handle) = self.pending.take_if(|h| h.is_finished()) {
+            self.finish(handle, state);
+        }
+
+        if self.pending.is_some() || self.suspended {
+            return;
+        }
+
+        let Some(modified_at) = state.modified_at else {
+            return;
+        };
+        if modified_at.elapsed() < AUTOSAVE_DELAY {
+            return;
+        }
+
+        let snapshot = state.clone();
+        state.mark_clean();
+        self.pending = Some(std::thread::spawn(move || {
+            snapshot.save().map_err(|e| e.to_string())?;
+            Ok(snapshot.file_path().and_then(modified_time))
+        }));
+    }
+
+    /// Wait for a running background save to finish, e.g. before exiting
+    ///
+    /// `poll` marks the state clean as soon as the save starts, so a failed
+    /// save only shows up as a dirty state once it has been collected here.
+    pub fn flush(&mut self, state: &mut AppState) {
+        if let Some(handle) = self.pending.take() {
+            self.finish(handle, state);
+        }
+    }
+
+    /// Save immediately on the calling thread, after waiting for any
+    /// background save to finish
+    pub fn save_now(&mut self, state: &mut AppState) -> Result<(), Box<dyn std::error::Error>> {
+        self.flush(state);
+
+        state.save()?;
+        state.mark_clean();
+        self.known_mtime = state.file_path().and_then(modified_time);
+        Ok(())
+    }
+
+    /// Collect the result of a background save, re-marking the state dirty if
+    /// it failed so the next poll retries
+    fn finish(&mut self, handle: JoinHandle<Result<Option<SystemTime>, String>>, state: &mut AppState) {
+        match join_save(handle) {
+            Ok(mtime) => self.known_mtime = mtime,
+            Err(e) => {
+                log::error!("Autosave failed: {}", e);
+                if !state.is_dirty() {
+                    state.mark_dirty();
+                }
+            }
+        }
+    }
+
+    /// Treat the state's file as it is on disk now as our own version, e.g.
+    /// right after loading it
+    ///
+    /// A background save still running for a previously watched state is
+    /// waited for first, so its result cannot later be taken for this file.
+    /// Callers replacing the state should `flush` before doing so, which
+    /// also retries a failed save.
+    pub fn watch(&mut self, state: &AppState) {
+        if let Some(handle) = self.pending.take() {
+            if let Err(e) = join_save(handle) {
+                log::error!("Autosave of the previous state failed: {}", e);
+            }
+        }
+        self.known_mtime = state.file_path().and_then(modified_time);
+        self.suspended = false;
+    }
+
+    /// Pause or resume autosaving
+    pub fn set_suspended(&mut self, suspended: bool) {
+        self.suspended = suspended;
+    }
+
+    /// Whether the state file was changed by another program since we last
+    /// wrote or loaded it
+    ///
+    /// Checks at most once per `WATCH_INTERVAL` and never while a save of our
+    /// own is running or a previous change is still unresolved.
+    pub fn check_external_change(&mut self, state: &AppState) -> bool {
+        if self.pending.is_some() || self.suspended || self.last_check.elapsed() < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let Some(mtime) = state.file_path().and_then(modified_time) else {
+            return false;
+        };
+        self.known_mtime != Some(mtime)
+    }
+}
+
+/// Wait for a background save, returning the saved file's new modification
+/// time
+fn join_save(handle: JoinHandle<Result<Option<SystemTime>, String>>) -> Result<Option<SystemTime>, String> {
+    handle
+        .join()
+        .unwrap_or_else(|_| Err("autosave thread panicked".to_string()))
+}
+
+/// Modification time of a file, if it exists
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Replace NaN and infinite values with a fallback
+fn finite_or(value: f32, fallback: f32) -> f32 {
+    if value.is_finite() {
+        value
+    } else {
+        fallback
+    }
+}
+
+/// Directory holding all persisted application data, created on demand
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|mut path| {
+        path.push("focus-desktop-simulator");
+        fs::create_dir_all(&path).ok();
+        path
+    })
+}
+
+/// Write a file and flush it to disk before returning
+fn write_synced(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// Replace a file atomically via a synced temporary file and a rename
+pub fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    write_synced(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)?;
+    sync_parent_dir(path);
+    Ok(())
+}
+
+/// Turn a display name into a file name stem: lowercase ASCII letters and
+/// digits joined by dashes, or `fallback` if nothing is left
+pub fn file_slug(name: &str, fallback: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        fallback.to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Path of a backup generation, e.g. `desk-state.1.json`
+fn generation_path(path: &Path, generation: u32) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.{}.json", stem, generation))
+}
+
+/// Flush the directory entry after a rename so it survives power loss
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+/// Directory handles cannot be synced on this platform; the rename is
+/// already atomic
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+/// Upgrade raw state JSON in place to the current format version
+pub fn migrate_value(value: &mut Value) -> Result<(), String> {
+    if !value.is_object() {
+        return Err("top-level value is not an object".to_string());
+    }
+
+    let version = detect_version(value)?;
+    if version > CURRENT_STATE_VERSION {
+        log::warn!(
+            "State file version {} is newer than supported version {}, \
+            attempting to load anyway",
+            version,
+            CURRENT_STATE_VERSION
+        );
+    } else if version < CURRENT_STATE_VERSION {
+        for from in version..CURRENT_STATE_VERSION {
+            let step = MIGRATIONS[(from - 1) as usize];
+            step(value)
+                .map_err(|e| format!("migration v{} -> v{} failed: {}", from, from + 1, e))?;
+            value["version"] = Value::from(from + 1);
+        }
+        log::info!(
+            "Migrated state file from version {} to {}",
+            version,
+            CURRENT_STATE_VERSION
+        );
+    }
+
+    Ok(())
+}
+
+/// Read the format version of a raw state file
+///
+/// Files written before versioning was introduced have no `version` field and
+/// are treated as version 1.
+fn detect_version(value: &Value) -> Result<u32, String> {
+    match value.get("version") {
+        None | Some(Value::Null) => Ok(1),
+        Some(v) => v
+            .as_u64()
+            .filter(|&v| v >= 1)
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("invalid version field: {}", v)),
+    }
+}
+
+/// v1 -> v2: fill in top-level fields that older files may lack
+///
+/// Version 1 files were written by builds that did not always emit the
+/// collision multipliers or `next_object_id`, which made the whole file fail
+/// to parse. The next ID is derived from the highest existing object ID.
+fn migrate_v1_to_v2(value: &mut Value) -> Result<(), String> {
+    let root = value
+        .as_object_mut()
+        .ok_or("top-level value is not an object")?;
+
+    let objects = root
+        .entry("objects")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    let objects = objects.as_array().ok_or("`objects` is not an array")?;
+    let max_id = objects
+        .iter()
+        .filter_map(|o| o.get("id").and_then(Value::as_u64))
+        .max()
+        .unwrap_or(0);
+
+    root.entry("collision_radius_multiplier")
+        .or_insert_with(|| Value::from(1.0));
+    root.entry("collision_height_multiplier")
+        .or_insert_with(|| Value::from(1.0));
+    root.entry("next_object_id")
+        .or_insert_with(|| Value::from(max_id + 1));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desk_object::ObjectType;
+    use serde_json::json;
+
+    fn object(id: u64) -> DeskObject {
+        DeskObject::new(id, ObjectType::Coffee, Vec3::ZERO)
+    }
+
+    #[test]
+    fn detect_version_defaults_to_v1() {
+        assert_eq!(detect_version(&json!({ "objects": [] })), Ok(1));
+        assert_eq!(detect_version(&json!({ "version": null })), Ok(1));
+        assert_eq!(detect_version(&json!({ "version": 2 })), Ok(2));
+    }
+
+    #[test]
+    fn detect_version_rejects_invalid_values() {
+        for version in [json!(0), json!(-1), json!(1.5), json!("2"), json!(u64::MAX)] {
+            assert!(
+                detect_version(&json!({ "version": version })).is_err(),
+                "accepted version {}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn migrate_fills_missing_v1_fields() {
+        let objects = vec![
+            serde_json::to_value(object(3)).unwrap(),
+            serde_json::to_value(object(7)).unwrap(),
+        ];
+        let mut value = json!({ "objects": objects });
+        migrate_value(&mut value).unwrap();
+
+        assert_eq!(value["version"], json!(CURRENT_STATE_VERSION));
+        assert_eq!(value["next_object_id"], json!(8));
+        let state: AppState = serde_json::from_value(value).unwrap();
+        assert_eq!(state.objects.len(), 2);
+        assert_eq!(state.collision_radius_multiplier, 1.0);
+        assert_eq!(state.collision_height_multiplier, 1.0);
+    }
+
+    #[test]
+    fn migrate_keeps_existing_v1_fields() {
+        let mut value = json!({
+            "objects": [],
+            "collision_radius_multiplier": 1.5,
+            "next_object_id": 20,
+        });
+        migrate_value(&mut value).unwrap();
+
+        assert_eq!(value["collision_radius_multiplier"], json!(1.5));
+        assert_eq!(value["next_object_id"], json!(20));
+        assert_eq!(value["objects"], json!([]));
+    }
+
+    #[test]
+    fn migrate_creates_missing_objects() {
+        let mut value = json!({});
+        migrate_value(&mut value).unwrap();
+        assert_eq!(value["objects"], json!([]));
+        assert_eq!(value["next_object_id"], json!(1));
+    }
+
+    #[test]
+    fn migrate_rejects_malformed_files() {
+        assert!(migrate_value(&mut json!([])).is_err());
+        assert!(migrate_value(&mut json!({ "version": "one" })).is_err());
+        assert!(migrate_value(&mut json!({ "objects": {} })).is_err());
+    }
+
+    #[test]
+    fn migrate_leaves_newer_versions_untouched() {
+        let original = json!({ "version": CURRENT_STATE_VERSION + 1, "objects": [] });
+        let mut value = original.clone();
+        migrate_value(&mut value).unwrap();
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn newer_file_is_kept_before_first_save() {
+        let dir = std::env::temp_dir().join(format!("focus-desk-newer-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("desk-state.json");
+        let original = json!({
+            "version": CURRENT_STATE_VERSION + 1,
+            "objects": [],
+            "collision_radius_multiplier": 1.0,
+            "collision_height_multiplier": 1.0,
+            "next_object_id": 1,
+            "added_later": true,
+        })
+        .to_string();
+        fs::write(&path, &original).unwrap();
+
+        let state = AppState::load_from(path.clone());
+        assert_eq!(state.version, CURRENT_STATE_VERSION);
+        state.save().unwrap();
+        state.save().unwrap();
+
+        let copy_path = dir.join(format!("desk-state.v{}.json", CURRENT_STATE_VERSION + 1));
+        assert_eq!(fs::read_to_string(&copy_path).unwrap(), original);
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], json!(CURRENT_STATE_VERSION));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn repair_reassigns_duplicate_and_zero_ids() {
+        let mut state = AppState::new();
+        state.objects = vec![object(2), object(2), object(0), object(5)];
+        state.next_object_id = 6;
+
+        let repairs = state.repair();
+
+        let ids: Vec<u64> = state.objects.iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![2, 6, 7, 5]);
+        assert_eq!(state.next_object_id, 8);
+        assert_eq!(repairs.len(), 3);
+    }
+
+    #[test]
+    fn repair_raises_next_object_id() {
+        let mut state = AppState::new();
+        state.objects = vec![object(4), object(9)];
+        state.next_object_id = 3;
+
+        let repairs = state.repair();
+
+        assert_eq!(state.next_object_id, 10);
+        assert_eq!(repairs.len(), 1);
+    }
+
+    #[test]
+    fn repair_leaves_valid_state_alone() {
+        let mut state = AppState::new();
+        state.objects = vec![object(1), object(2)];
+        state.next_object_id = 3;
+
+        assert!(state.repair().is_empty());
+        assert_eq!(state.next_object_id, 3);
+    }
+
+    #[test]
+    fn repair_fixes_transforms() {
+        let mut state = AppState::new();
+        let mut obj = object(1);
+        obj.position = Vec3::new(f32::NAN, -2.0, 1.0);
+        obj.rotation = Quat::from_xyzw(0.0, 0.0, 0.0, 0.0);
+        obj.scale = 500.0;
+        state.objects = vec![obj];
+        state.next_object_id = 2;
+
+        state.repair();
+
+        let obj = &state.objects[0];
+        assert_eq!(obj.position, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(obj.rotation, Quat::IDENTITY);
+        assert_eq!(obj.scale, MAX_SCALE);
     }
 }
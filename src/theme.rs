@@ -0,0 +1,399 @@
+//! Visual themes module
+//!
+//! A theme bundles the scene colors, desk color, object color palettes, UI
+//! colors and pixelation settings into one switchable unit. Built-in themes
+//! ship with the app; user themes are TOML files in the `themes` folder of
+//! the config directory.
+
+use crate::config::{config_file_path, hex_color, round_floats, ColorConfig, Config, ConfigError, PixelationConfig};
+use crate::state::{file_slug, write_atomic};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Theme used when the config names none or an unknown one
+pub const DEFAULT_THEME_NAME: &str = "Night study";
+
+/// A named color offered in the customization palettes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaletteColor {
+    /// Color (RGB hex)
+    #[serde(with = "hex_color")]
+    pub color: u32,
+    /// Display name
+    pub name: String,
+}
+
+/// Colors of the egui panels and buttons
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiColors {
+    /// Panel and window background (RGB hex)
+    #[serde(with = "hex_color")]
+    pub panel: u32,
+    /// Panel opacity (0-255)
+    pub panel_alpha: u8,
+    /// Buttons, highlights and selections (RGB hex)
+    #[serde(with = "hex_color")]
+    pub accent: u32,
+}
+
+impl Default for UiColors {
+    fn default() -> Self {
+        Self {
+            panel: 0x1a1a2e,
+            panel_alpha: 242,
+            accent: 0x4f46e5,
+        }
+    }
+}
+
+/// A complete visual theme
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Display name, also used to select the theme in the config
+    pub name: String,
+    /// Scene colors (background, lights, floor)
+    pub colors: ColorConfig,
+    /// Desk color (RGB hex)
+    #[serde(with = "hex_color")]
+    pub desk_color: u32,
+    /// Main colors offered when customizing an object
+    pub main_palette: Vec<PaletteColor>,
+    /// Accent colors offered when customizing an object
+    pub accent_palette: Vec<PaletteColor>,
+    /// UI panel colors
+    pub ui: UiColors,
+    /// Pixelation effect settings
+    pub pixelation: PixelationConfig,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::night_study()
+    }
+}
+
+/// Build a palette from `(color, name)` pairs
+fn palette(colors: &[(u32, &str)]) -> Vec<PaletteColor> {
+    colors
+        .iter()
+        .map(|(color, name)| PaletteColor {
+            color: *color,
+            name: name.to_string(),
+        })
+        .collect()
+}
+
+impl Theme {
+    /// The original dark blue look
+    fn night_study() -> Self {
+        Self {
+            name: DEFAULT_THEME_NAME.to_string(),
+            colors: ColorConfig::default(),
+            desk_color: 0x8b6914,
+            main_palette: palette(&[
+                (0xEF4444, "Red"),
+                (0xF97316, "Orange"),
+                (0xEAB308, "Yellow"),
+                (0x22C55E, "Green"),
+                (0x3B82F6, "Blue"),
+                (0x8B5CF6, "Purple"),
+                (0xEC4899, "Pink"),
+                (0xFFFFFF, "White"),
+                (0x64748B, "Gray"),
+                (0x1E293B, "Dark"),
+            ]),
+            accent_palette: palette(&[
+                (0xFBBF24, "Amber"),
+                (0xA3E635, "Lime"),
+                (0x2DD4BF, "Teal"),
+                (0x60A5FA, "Light Blue"),
+                (0xC084FC, "Lavender"),
+                (0xF472B6, "Rose"),
+                (0xFB923C, "Peach"),
+                (0xD4D4D4, "Silver"),
+                (0x000000, "Black"),
+                (0xFFFFFF, "White"),
+            ]),
+            ui: UiColors::default(),
+            pixelation: PixelationConfig::default(),
+        }
+    }
+
+    /// Daylight room with a light wooden desk
+    fn bright_office() -> Self {
+        Self {
+            name: "Bright office".to_string(),
+            colors: ColorConfig {
+                background: 0xdde4ec,
+                ambient: 0x8c919c,
+                directional: 0xfff4e0,
+                ground: 0xa8b0bc,
+            },
+            desk_color: 0xc8a26b,
+            main_palette: palette(&[
+                (0xDC2626, "Red"),
+                (0xEA580C, "Orange"),
+                (0xCA8A04, "Mustard"),
+                (0x16A34A, "Green"),
+                (0x2563EB, "Blue"),
+                (0x0891B2, "Cyan"),
+                (0x7C3AED, "Violet"),
+                (0xF8FAFC, "White"),
+                (0x94A3B8, "Gray"),
+                (0x334155, "Slate"),
+            ]),
+            accent_palette: palette(&[
+                (0xFDE68A, "Butter"),
+                (0xBBF7D0, "Mint"),
+                (0xBAE6FD, "Sky"),
+                (0xFBCFE8, "Blush"),
+                (0xE9D5FF, "Lilac"),
+                (0xFED7AA, "Apricot"),
+                (0xE5E7EB, "Silver"),
+                (0x78350F, "Walnut"),
+                (0x000000, "Black"),
+                (0xFFFFFF, "White"),
+            ]),
+            ui: UiColors {
+                panel: 0x334155,
+                panel_alpha: 235,
+                accent: 0x2563eb,
+            },
+            pixelation: PixelationConfig {
+                enabled: false,
+                pixel_size: 2,
+                normal_edge_strength: 0.2,
+                depth_edge_strength: 0.2,
+            },
+        }
+    }
+
+    /// Neon colors on a near-black scene
+    fn cyber() -> Self {
+        Self {
+            name: "Cyber".to_string(),
+            colors: ColorConfig {
+                background: 0x0a0014,
+                ambient: 0x3a1a5c,
+                directional: 0x40e0ff,
+                ground: 0x140028,
+            },
+            desk_color: 0x1f1f3a,
+            main_palette: palette(&[
+                (0xFF00AA, "Magenta"),
+                (0x00F0FF, "Cyan"),
+                (0x39FF14, "Neon Green"),
+                (0xFFE600, "Volt"),
+                (0xFF3131, "Laser Red"),
+                (0x9D00FF, "Ultraviolet"),
+                (0xFF6EC7, "Hot Pink"),
+                (0xE0E0FF, "Ice"),
+                (0x2E2E4F, "Gunmetal"),
+                (0x0D0D1A, "Void"),
+            ]),
+            accent_palette: palette(&[
+                (0x00F0FF, "Cyan"),
+                (0xFF00AA, "Magenta"),
+                (0x39FF14, "Neon Green"),
+                (0xFFE600, "Volt"),
+                (0x9D00FF, "Ultraviolet"),
+                (0xFF8C00, "Sodium"),
+                (0xC0C0FF, "Chrome"),
+                (0x404060, "Steel"),
+                (0x000000, "Black"),
+                (0xFFFFFF, "White"),
+            ]),
+            ui: UiColors {
+                panel: 0x120022,
+                panel_alpha: 230,
+                accent: 0xff00aa,
+            },
+            pixelation: PixelationConfig {
+                enabled: true,
+                pixel_size: 6,
+                normal_edge_strength: 0.6,
+                depth_edge_strength: 0.6,
+            },
+        }
+    }
+
+    /// Make a theme from the current scene settings, taking palettes and UI
+    /// colors from `base`
+    pub fn capture(name: &str, config: &Config, base: &Theme) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            colors: config.colors.clone(),
+            desk_color: config.desk.color,
+            main_palette: base.main_palette.clone(),
+            accent_palette: base.accent_palette.clone(),
+            ui: base.ui.clone(),
+            pixelation: config.pixelation.clone(),
+        }
+    }
+
+    /// Copy the theme's scene settings into a config and select the theme
+    pub fn apply_to(&self, config: &mut Config) {
+        config.theme = self.name.clone();
+        config.colors = self.colors.clone();
+        config.desk.color = self.desk_color;
+        config.pixelation = self.pixelation.clone();
+    }
+
+    /// Reset invalid settings to the default theme's, returning an error for
+    /// each, as `Config` does for the config file
+    pub fn validate(&mut self) -> Vec<ConfigError> {
+        let defaults = Self::default();
+        let mut errors = self.pixelation.validate();
+
+        for (field, palette, default) in [
+            ("main_palette", &mut self.main_palette, defaults.main_palette),
+            ("accent_palette", &mut self.accent_palette, defaults.accent_palette),
+        ] {
+            if palette.is_empty() {
+                errors.push(ConfigError::new(field, "must not be empty, using default"));
+                *palette = default;
+            }
+        }
+
+        errors
+    }
+
+    /// Accent color for buttons drawn by the UI
+    pub fn accent(&self) -> egui::Color32 {
+        color32(self.ui.accent, 255)
+    }
+
+    /// egui visuals for this theme
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = egui::Visuals::dark();
+        let panel = color32(self.ui.panel, self.ui.panel_alpha);
+        visuals.window_fill = panel;
+        visuals.panel_fill = panel;
+        visuals.selection.bg_fill = self.accent();
+        visuals.hyperlink_color = self.accent();
+        visuals
+    }
+}
+
+/// Convert a hex RGB color and alpha to an egui color
+fn color32(hex: u32, alpha: u8) -> egui::Color32 {
+    egui::Color32::from_rgba_unmultiplied((hex >> 16) as u8, (hex >> 8) as u8, hex as u8, alpha)
+}
+
+/// Built-in and user themes
+pub struct ThemeLibrary {
+    /// Folder holding user theme files, if the config directory is known
+    dir: Option<PathBuf>,
+    themes: Vec<Theme>,
+}
+
+impl ThemeLibrary {
+    /// Load the built-in themes followed by all user theme files
+    ///
+    /// A user theme with the same name as a built-in one replaces it.
+    pub fn load() -> Self {
+        let dir = config_file_path().and_then(|path| path.parent().map(|dir| dir.join("themes")));
+        let mut library = Self {
+            dir,
+            themes: vec![Theme::night_study(), Theme::bright_office(), Theme::cyber()],
+        };
+
+        let Some(dir) = library.dir.clone() else {
+            return library;
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return library;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let theme = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| toml::from_str::<Theme>(&content).map_err(|e| e.to_string()));
+            match theme {
+                Ok(mut theme) => {
+                    if theme.name.trim().is_empty() {
+                        theme.name = path
+                            .file_stem()
+                            .map(|s| s.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                    }
+                    for error in theme.validate() {
+                        log::warn!("Theme {:?}: {}", path, error);
+                    }
+                    log::info!("Loaded theme \"{}\" from {:?}", theme.name, path);
+                    library.insert(theme);
+                }
+                Err(e) => log::warn!("Could not load theme {:?}: {}", path, e),
+            }
+        }
+
+        library
+    }
+
+    /// Add a theme, replacing any theme with the same name
+    fn insert(&mut self, theme: Theme) {
+        match self.themes.iter_mut().find(|t| t.name == theme.name) {
+            Some(existing) => *existing = theme,
+            None => self.themes.push(theme),
+        }
+    }
+
+    /// Names of all themes in display order
+    pub fn names(&self) -> Vec<String> {
+        self.themes.iter().map(|t| t.name.clone()).collect()
+    }
+
+    /// Look up a theme by name
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|t| t.name == name)
+    }
+
+    /// Look up a theme by name, falling back to the default theme
+    pub fn get_or_default(&self, name: &str) -> &Theme {
+        self.get(name)
+            .or_else(|| self.get(DEFAULT_THEME_NAME))
+            .unwrap_or(&self.themes[0])
+    }
+
+    /// Save a theme as a user theme file and add it to the library
+    pub fn save_user_theme(&mut self, theme: Theme) -> Result<PathBuf, String> {
+        if theme.name.is_empty() {
+            return Err("theme name cannot be empty".to_string());
+        }
+        let dir = self.dir.clone().ok_or("could not determine config directory")?;
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        // Names like "My Theme" and "my theme" share a slug; only overwrite
+        // the file of the theme being saved
+        let slug = file_slug(&theme.name, "theme");
+        let mut path = dir.join(format!("{}.toml", slug));
+        let mut suffix = 2;
+        while path.exists() && theme_name_in_file(&path).as_deref() != Some(theme.name.as_str()) {
+            path = dir.join(format!("{}-{}.toml", slug, suffix));
+            suffix += 1;
+        }
+
+        let mut table = toml::Table::try_from(&theme).map_err(|e| e.to_string())?;
+        round_floats(&mut table);
+        let content = toml::to_string(&table).map_err(|e| e.to_string())?;
+        write_atomic(&path, content.as_bytes()).map_err(|e| format!("could not write theme: {}", e))?;
+        log::info!("Saved theme \"{}\" to {:?}", theme.name, path);
+
+        self.insert(theme);
+        Ok(path)
+    }
+}
+
+/// Name stored in a theme file, if it can be read
+fn theme_name_in_file(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    toml::from_str::<Theme>(&content).ok().map(|theme| theme.name)
+}
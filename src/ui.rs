@@ -3,9 +3,17 @@
 //! Implements:
 //! - Left sidebar: Object palette with categories (like the reference Electron app)
 //! - Right sidebar: Object customization panel (colors, delete)
+//! - Settings window: Live editing of the user config
+//! - Bindings window: Editing the key and mouse bindings
 
+use crate::bindings::{parse_bindings, Action, KeyMap};
+use crate::bundle::ImportMode;
+use crate::camera::{CameraMode, MAX_FOV_DEGREES, MIN_FOV_DEGREES};
+use crate::config::{Config, DeskShape};
+use crate::theme::{PaletteColor, Theme};
 use crate::desk_object::ObjectType;
 use egui::{Color32, RichText, Vec2};
+use std::ops::RangeInclusive;
 
 /// Palette category for organizing object types
 #[derive(Debug, Clone)]
@@ -24,33 +32,6 @@ pub struct PaletteVariant {
     pub icon: &'static str,
 }
 
-/// Color presets for object customization
-pub const COLOR_PRESETS: &[(u32, &str)] = &[
-    (0xEF4444, "Red"),
-    (0xF97316, "Orange"),
-    (0xEAB308, "Yellow"),
-    (0x22C55E, "Green"),
-    (0x3B82F6, "Blue"),
-    (0x8B5CF6, "Purple"),
-    (0xEC4899, "Pink"),
-    (0xFFFFFF, "White"),
-    (0x64748B, "Gray"),
-    (0x1E293B, "Dark"),
-];
-
-pub const ACCENT_COLOR_PRESETS: &[(u32, &str)] = &[
-    (0xFBBF24, "Amber"),
-    (0xA3E635, "Lime"),
-    (0x2DD4BF, "Teal"),
-    (0x60A5FA, "Light Blue"),
-    (0xC084FC, "Lavender"),
-    (0xF472B6, "Rose"),
-    (0xFB923C, "Peach"),
-    (0xD4D4D4, "Silver"),
-    (0x000000, "Black"),
-    (0xFFFFFF, "White"),
-];
-
 /// UI state for menus
 pub struct UiState {
     /// Whether the left sidebar (palette) is open
@@ -65,6 +46,55 @@ pub struct UiState {
     pub current_main_color: u32,
     /// Current accent color for selected object
     pub current_accent_color: u32,
+    /// Whether the desk has changes that are not yet saved
+    pub has_unsaved_changes: bool,
+    /// Names of all saved layouts
+    pub layout_names: Vec<String>,
+    /// Name of the active layout
+    pub active_layout: String,
+    /// Text entered in the layout name field
+    pub layout_name_input: String,
+    /// Error from the last layout operation
+    pub layout_error: Option<String>,
+    /// Whether the user asked to delete the active layout and has yet to
+    /// confirm it
+    pub confirm_layout_delete: bool,
+    /// State file given with `--state`, which replaces the layouts
+    pub state_file_override: Option<String>,
+    /// Path entered for bundle export/import
+    pub bundle_path_input: String,
+    /// Result of the last bundle export/import
+    pub bundle_status: Option<String>,
+    /// The state file changed on disk while there were unsaved changes
+    pub external_change_pending: bool,
+    /// Result of the last config file write
+    pub config_status: Option<String>,
+    /// Whether the settings window is open
+    pub settings_open: bool,
+    /// Names of all available themes
+    pub theme_names: Vec<String>,
+    /// Name of the selected theme
+    pub active_theme: String,
+    /// Text entered in the theme name field
+    pub theme_name_input: String,
+    /// Main colors offered by the active theme
+    pub main_palette: Vec<PaletteColor>,
+    /// Accent colors offered by the active theme
+    pub accent_palette: Vec<PaletteColor>,
+    /// Accent color of the active theme's buttons
+    pub accent: Color32,
+    /// One line per action describing its bindings
+    pub control_hints: Vec<String>,
+    /// Whether the bindings window is open
+    pub bindings_open: bool,
+    /// Binding text being edited, one entry per `Action::ALL`
+    pub binding_drafts: Vec<String>,
+    /// Active camera mode
+    pub camera_mode: CameraMode,
+    /// Names of the saved camera views
+    pub bookmark_names: Vec<String>,
+    /// Text entered in the view name field
+    pub bookmark_name_input: String,
 }
 
 impl Default for UiState {
@@ -208,6 +238,30 @@ impl UiState {
             selected_object_id: None,
             current_main_color: 0xFFFFFF,
             current_accent_color: 0x1E293B,
+            has_unsaved_changes: false,
+            layout_names: Vec::new(),
+            active_layout: String::new(),
+            layout_name_input: String::new(),
+            layout_error: None,
+            confirm_layout_delete: false,
+            state_file_override: None,
+            bundle_path_input: String::new(),
+            bundle_status: None,
+            external_change_pending: false,
+            config_status: None,
+            settings_open: false,
+            theme_names: Vec::new(),
+            active_theme: String::new(),
+            theme_name_input: String::new(),
+            main_palette: Vec::new(),
+            accent_palette: Vec::new(),
+            accent: Color32::from_rgb(79, 70, 229),
+            control_hints: Vec::new(),
+            bindings_open: false,
+            binding_drafts: Vec::new(),
+            camera_mode: CameraMode::default(),
+            bookmark_names: Vec::new(),
+            bookmark_name_input: String::new(),
         }
     }
 
@@ -230,6 +284,52 @@ impl UiState {
         self.selected_object_id = None;
         self.right_sidebar_open = false;
     }
+
+    /// Names of the palette categories that are currently expanded
+    pub fn expanded_categories(&self) -> Vec<String> {
+        self.categories
+            .iter()
+            .filter(|c| c.expanded)
+            .map(|c| c.name.to_string())
+            .collect()
+    }
+
+    /// Expand exactly the named palette categories
+    pub fn set_expanded_categories(&mut self, names: &[String]) {
+        for category in &mut self.categories {
+            category.expanded = names.iter().any(|n| n == category.name);
+        }
+    }
+
+    /// Take over the palettes and button color of a theme
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.active_theme = theme.name.clone();
+        self.main_palette = theme.main_palette.clone();
+        self.accent_palette = theme.accent_palette.clone();
+        self.accent = theme.accent();
+    }
+
+    /// Translucent accent fill for secondary buttons
+    fn accent_fill(&self) -> Color32 {
+        let [r, g, b, _] = self.accent.to_array();
+        Color32::from_rgba_unmultiplied(r, g, b, 51)
+    }
+
+    /// Clear the layout name field and error after a successful operation
+    pub fn layout_finished(&mut self) {
+        self.layout_name_input.clear();
+        self.layout_error = None;
+        self.confirm_layout_delete = false;
+    }
+}
+
+/// How to resolve a state file that changed on disk under unsaved edits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalChangeChoice {
+    /// Discard in-app changes and load the file
+    LoadFromDisk,
+    /// Keep in-app changes and overwrite the file on the next save
+    KeepMine,
 }
 
 /// UI action that can be returned from rendering
@@ -245,6 +345,41 @@ pub enum UiAction {
     ChangeAccentColor(u64, u32),
     /// Clear all objects from the desk
     ClearAll,
+    /// Save the desk state immediately
+    SaveNow,
+    /// Write the effective config to the config directory
+    WriteConfig,
+    /// Replace the running config with edited settings
+    UpdateSettings(Box<Config>),
+    /// Apply the named theme
+    SwitchTheme(String),
+    /// Save the current scene colors as a user theme with the given name
+    SaveTheme(String),
+    /// Switch to the named layout
+    SwitchLayout(String),
+    /// Create a new empty layout with the given name
+    CreateLayout(String),
+    /// Copy the active layout under the given name
+    DuplicateLayout(String),
+    /// Rename the active layout
+    RenameLayout(String),
+    /// Delete the active layout
+    DeleteLayout,
+    /// Export the desk to a bundle file
+    ExportBundle(String),
+    /// Import a bundle file into the desk
+    ImportBundle(String, ImportMode),
+    /// Resolve a conflicting external change to the state file
+    ResolveExternalChange(ExternalChangeChoice),
+    /// Switch the camera mode
+    SetCameraMode(CameraMode),
+    /// Move the camera to the saved view at this index
+    GoToBookmark(usize),
+    /// Save the current camera view under the given name, replacing a view
+    /// of the same name
+    SaveBookmark(String),
+    /// Delete the saved view at this index
+    DeleteBookmark(usize),
     /// Close the customization panel
     CloseCustomization,
     /// No action
@@ -260,7 +395,7 @@ pub fn render_left_sidebar(ctx: &egui::Context, ui_state: &mut UiState) -> Vec<U
         .fixed_pos(egui::pos2(20.0, 20.0))
         .show(ctx, |ui| {
             let button = egui::Button::new(RichText::new("☰").size(24.0).color(Color32::WHITE))
-                .fill(Color32::from_rgb(79, 70, 229))
+                .fill(ui_state.accent)
                 .min_size(Vec2::new(50.0, 50.0));
 
             if ui.add(button).clicked() {
@@ -286,6 +421,15 @@ pub fn render_left_sidebar(ctx: &egui::Context, ui_state: &mut UiState) -> Vec<U
                 ui.separator();
                 ui.add_space(10.0);
 
+                render_layout_picker(ui, ui_state, &mut actions);
+
+                ui.add_space(10.0);
+                render_camera_modes(ui, ui_state, &mut actions);
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
                 // Palette categories
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     let mut category_clicked = None;
@@ -318,7 +462,7 @@ pub fn render_left_sidebar(ctx: &egui::Context, ui_state: &mut UiState) -> Vec<U
                                             .size(12.0)
                                             .color(Color32::from_gray(200)),
                                     )
-                                    .fill(Color32::from_rgba_unmultiplied(79, 70, 229, 51))
+                                    .fill(ui_state.accent_fill())
                                     .min_size(Vec2::new(ui.available_width() - 30.0, 35.0));
 
                                     if ui.add(variant_button).clicked() {
@@ -343,10 +487,51 @@ pub fn render_left_sidebar(ctx: &egui::Context, ui_state: &mut UiState) -> Vec<U
 
                     ui.add_space(20.0);
 
-                    // Clear all button
+                    // Save status and save button
                     ui.separator();
                     ui.add_space(10.0);
 
+                    let (status, status_color) = if ui_state.has_unsaved_changes {
+                        ("● Unsaved changes", Color32::from_rgb(251, 191, 36))
+                    } else {
+                        ("✔ All changes saved", Color32::from_gray(120))
+                    };
+                    ui.label(RichText::new(status).size(11.0).color(status_color));
+                    ui.add_space(5.0);
+
+                    let save_button = egui::Button::new(
+                        RichText::new("💾 Save Now")
+                            .size(14.0)
+                            .color(Color32::from_gray(220)),
+                    )
+                    .fill(ui_state.accent_fill())
+                    .min_size(Vec2::new(ui.available_width() - 20.0, 40.0));
+
+                    if ui.add(save_button).clicked() {
+                        actions.push(UiAction::SaveNow);
+                    }
+
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(RichText::new("⚙ Settings").size(12.0)).clicked() {
+                            ui_state.settings_open = !ui_state.settings_open;
+                        }
+                        if ui.button(RichText::new("Write config file").size(12.0)).clicked() {
+                            actions.push(UiAction::WriteConfig);
+                        }
+                    });
+                    if let Some(status) = &ui_state.config_status {
+                        ui.label(RichText::new(status).size(11.0).color(Color32::from_gray(180)));
+                    }
+
+                    ui.add_space(10.0);
+
+                    render_bundle_controls(ui, ui_state, &mut actions);
+
+                    ui.add_space(10.0);
+
+                    // Clear all button
+
                     let clear_button = egui::Button::new(
                         RichText::new("🗑️ Clear All Objects")
                             .size(14.0)
@@ -365,18 +550,411 @@ pub fn render_left_sidebar(ctx: &egui::Context, ui_state: &mut UiState) -> Vec<U
                     ui.separator();
                     ui.add_space(10.0);
                     ui.label(RichText::new("Controls:").size(12.0).color(Color32::from_gray(150)));
-                    ui.label(RichText::new("• Click+Drag to move").size(11.0).color(Color32::from_gray(120)));
-                    ui.label(RichText::new("• Scroll to rotate").size(11.0).color(Color32::from_gray(120)));
-                    ui.label(RichText::new("• Shift+Scroll to scale").size(11.0).color(Color32::from_gray(120)));
-                    ui.label(RichText::new("• Right-click to customize").size(11.0).color(Color32::from_gray(120)));
-                    ui.label(RichText::new("• Delete to remove").size(11.0).color(Color32::from_gray(120)));
+                    for hint in &ui_state.control_hints {
+                        ui.label(RichText::new(format!("• {}", hint)).size(11.0).color(Color32::from_gray(120)));
+                    }
+                });
+            });
+    }
+
+    actions
+}
+
+/// Render one button per camera mode, highlighting the active one, and the
+/// saved views
+fn render_camera_modes(ui: &mut egui::Ui, ui_state: &mut UiState, actions: &mut Vec<UiAction>) {
+    ui.label(RichText::new("VIEW").size(11.0).color(Color32::from_gray(150)));
+    ui.add_space(5.0);
+    ui.horizontal_wrapped(|ui| {
+        for &mode in CameraMode::ALL {
+            let selected = mode == ui_state.camera_mode;
+            if ui.selectable_label(selected, mode.display_name()).clicked() && !selected {
+                actions.push(UiAction::SetCameraMode(mode));
+            }
+        }
+    });
+
+    ui.add_space(5.0);
+    for (index, name) in ui_state.bookmark_names.iter().enumerate() {
+        ui.horizontal(|ui| {
+            if ui.button(format!("📷 {}", name)).clicked() {
+                actions.push(UiAction::GoToBookmark(index));
+            }
+            if ui.small_button("✕").on_hover_text("Delete saved view").clicked() {
+                actions.push(UiAction::DeleteBookmark(index));
+            }
+        });
+    }
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut ui_state.bookmark_name_input)
+                .hint_text("View name")
+                .desired_width(ui.available_width() - 90.0),
+        );
+        let name = ui_state.bookmark_name_input.clone();
+        if ui.add_enabled(!name.trim().is_empty(), egui::Button::new("Save view")).clicked() {
+            actions.push(UiAction::SaveBookmark(name));
+        }
+    });
+}
+
+/// Render the layout picker with create/duplicate/rename/delete controls
+fn render_layout_picker(ui: &mut egui::Ui, ui_state: &mut UiState, actions: &mut Vec<UiAction>) {
+    ui.label(RichText::new("LAYOUT").size(11.0).color(Color32::from_gray(150)));
+    ui.add_space(5.0);
+
+    // Layout operations would act on a layout other than the open file
+    if let Some(file) = &ui_state.state_file_override {
+        ui.label(RichText::new(format!("Editing {}", file)).size(12.0).color(Color32::from_gray(200)));
+        ui.label(
+            RichText::new("Layouts are unavailable while a state file is given with --state.")
+                .size(11.0)
+                .color(Color32::from_gray(150)),
+        );
+        return;
+    }
+
+    let mut selected = ui_state.active_layout.clone();
+    egui::ComboBox::from_id_salt("layout_picker")
+        .selected_text(&selected)
+        .width(ui.available_width() - 20.0)
+        .show_ui(ui, |ui| {
+            for name in &ui_state.layout_names {
+                ui.selectable_value(&mut selected, name.clone(), name);
+            }
+        });
+    if selected != ui_state.active_layout {
+        ui_state.confirm_layout_delete = false;
+        actions.push(UiAction::SwitchLayout(selected));
+    }
+
+    ui.add_space(5.0);
+    ui.add(
+        egui::TextEdit::singleline(&mut ui_state.layout_name_input)
+            .hint_text("Layout name")
+            .desired_width(ui.available_width() - 20.0),
+    );
+
+    ui.horizontal(|ui| {
+        let name = ui_state.layout_name_input.clone();
+        let has_name = !name.trim().is_empty();
+        if ui.add_enabled(has_name, egui::Button::new("New")).clicked() {
+            actions.push(UiAction::CreateLayout(name.clone()));
+        }
+        if ui.add_enabled(has_name, egui::Button::new("Duplicate")).clicked() {
+            actions.push(UiAction::DuplicateLayout(name.clone()));
+        }
+        if ui.add_enabled(has_name, egui::Button::new("Rename")).clicked() {
+            actions.push(UiAction::RenameLayout(name));
+        }
+        let can_delete = ui_state.layout_names.len() > 1 && !ui_state.confirm_layout_delete;
+        if ui.add_enabled(can_delete, egui::Button::new("Delete")).clicked() {
+            ui_state.confirm_layout_delete = true;
+        }
+    });
+
+    // Deleting removes the layout's backups too, so ask first
+    if ui_state.confirm_layout_delete {
+        ui.label(
+            RichText::new(format!(
+                "Delete \"{}\" and all its backups? This cannot be undone.",
+                ui_state.active_layout
+            ))
+            .size(11.0)
+            .color(Color32::from_rgb(251, 191, 36)),
+        );
+        ui.horizontal(|ui| {
+            let delete = egui::Button::new(RichText::new("Delete").color(Color32::from_rgb(239, 68, 68)))
+                .fill(Color32::from_rgba_unmultiplied(239, 68, 68, 51));
+            if ui.add(delete).clicked() {
+                actions.push(UiAction::DeleteLayout);
+            }
+            if ui.button("Cancel").clicked() {
+                ui_state.confirm_layout_delete = false;
+            }
+        });
+    }
+
+    if let Some(error) = &ui_state.layout_error {
+        ui.label(RichText::new(error).size(11.0).color(Color32::from_rgb(239, 68, 68)));
+    }
+}
+
+/// Render the export/import controls for sharing desk bundles
+fn render_bundle_controls(ui: &mut egui::Ui, ui_state: &mut UiState, actions: &mut Vec<UiAction>) {
+    ui.label(RichText::new("SHARE").size(11.0).color(Color32::from_gray(150)));
+    ui.add_space(5.0);
+    ui.add(
+        egui::TextEdit::singleline(&mut ui_state.bundle_path_input)
+            .hint_text("Bundle file path")
+            .desired_width(ui.available_width() - 20.0),
+    );
+
+    ui.horizontal(|ui| {
+        let path = ui_state.bundle_path_input.trim().to_string();
+        let has_path = !path.is_empty();
+        if ui.add_enabled(has_path, egui::Button::new("Export")).clicked() {
+            actions.push(UiAction::ExportBundle(path.clone()));
+        }
+        if ui.add_enabled(has_path, egui::Button::new("Import (merge)")).clicked() {
+            actions.push(UiAction::ImportBundle(path.clone(), ImportMode::Merge));
+        }
+        if ui.add_enabled(has_path, egui::Button::new("Import (replace)")).clicked() {
+            actions.push(UiAction::ImportBundle(path, ImportMode::Replace));
+        }
+    });
+
+    if let Some(status) = &ui_state.bundle_status {
+        ui.label(RichText::new(status).size(11.0).color(Color32::from_gray(180)));
+    }
+}
+
+/// Render the prompt shown when the state file changed on disk while the
+/// desk has unsaved changes
+pub fn render_external_change_prompt(ctx: &egui::Context, ui_state: &UiState) -> Vec<UiAction> {
+    let mut actions = Vec::new();
+
+    if !ui_state.external_change_pending {
+        return actions;
+    }
+
+    egui::Window::new("Desk file changed")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("The desk file was changed by another program, but this desk has unsaved changes.");
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if ui.button("Load from disk").clicked() {
+                    actions.push(UiAction::ResolveExternalChange(ExternalChangeChoice::LoadFromDisk));
+                }
+                if ui.button("Keep my changes").clicked() {
+                    actions.push(UiAction::ResolveExternalChange(ExternalChangeChoice::KeepMine));
+                }
+            });
+        });
+
+    actions
+}
+
+/// Render the settings window, which edits a copy of the running config
+/// and reports every change so it can be applied immediately
+pub fn render_settings_window(ctx: &egui::Context, ui_state: &mut UiState, settings: &Config) -> Vec<UiAction> {
+    let mut actions = Vec::new();
+
+    if !ui_state.settings_open {
+        return actions;
+    }
+
+    let mut edited = settings.clone();
+    egui::Window::new("⚙ Settings")
+        .open(&mut ui_state.settings_open)
+        .resizable(false)
+        .default_pos(egui::pos2(300.0, 80.0))
+        .show(ctx, |ui| {
+            ui.label(RichText::new("THEME").size(11.0).color(Color32::from_gray(150)));
+            let mut selected = ui_state.active_theme.clone();
+            egui::ComboBox::from_id_salt("theme_picker")
+                .selected_text(&selected)
+                .show_ui(ui, |ui| {
+                    for name in &ui_state.theme_names {
+                        ui.selectable_value(&mut selected, name.clone(), name);
+                    }
                 });
+            if selected != ui_state.active_theme {
+                actions.push(UiAction::SwitchTheme(selected));
+            }
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut ui_state.theme_name_input)
+                        .hint_text("Theme name")
+                        .desired_width(140.0),
+                );
+                let name = ui_state.theme_name_input.trim().to_string();
+                if ui.add_enabled(!name.is_empty(), egui::Button::new("Save as theme")).clicked() {
+                    actions.push(UiAction::SaveTheme(name));
+                }
             });
+
+            ui.add_space(10.0);
+            ui.label(RichText::new("CAMERA").size(11.0).color(Color32::from_gray(150)));
+            ui.add(setting_slider(&mut edited.camera.fov, MIN_FOV_DEGREES..=MAX_FOV_DEGREES, "Field of view (°)"));
+            ui.add(setting_slider(&mut edited.camera.mouse_sensitivity, 0.1..=10.0, "Mouse-look sensitivity"));
+            ui.checkbox(&mut edited.camera.invert_y, "Invert mouse-look");
+
+            ui.add_space(10.0);
+            ui.label(RichText::new("DESK").size(11.0).color(Color32::from_gray(150)));
+            egui::ComboBox::from_label("Shape")
+                .selected_text(edited.desk.shape.display_name())
+                .show_ui(ui, |ui| {
+                    for shape in DeskShape::ALL {
+                        ui.selectable_value(&mut edited.desk.shape, *shape, shape.display_name());
+                    }
+                });
+            ui.add(setting_slider(&mut edited.desk.width, 0.5..=100.0, "Width").logarithmic(true));
+            ui.add(setting_slider(&mut edited.desk.depth, 0.5..=100.0, "Depth").logarithmic(true));
+            if edited.desk.shape == DeskShape::LShape {
+                let (width, depth) = (edited.desk.width, edited.desk.depth);
+                ui.add(setting_slider(&mut edited.desk.arm_width, 0.1_f32.min(width)..=width, "Return width"));
+                ui.add(setting_slider(&mut edited.desk.arm_depth, 0.1_f32.min(depth)..=depth, "Back depth"));
+            }
+            color_setting(ui, "Color", &mut edited.desk.color);
+
+            ui.add_space(10.0);
+            ui.label(RichText::new("COLORS").size(11.0).color(Color32::from_gray(150)));
+            color_setting(ui, "Background", &mut edited.colors.background);
+            color_setting(ui, "Ambient light", &mut edited.colors.ambient);
+            color_setting(ui, "Directional light", &mut edited.colors.directional);
+            color_setting(ui, "Floor", &mut edited.colors.ground);
+
+            ui.add_space(10.0);
+            ui.label(RichText::new("PHYSICS").size(11.0).color(Color32::from_gray(150)));
+            ui.add(setting_slider(&mut edited.physics.lift_height, 0.0..=2.0, "Lift height"));
+            ui.add(setting_slider(&mut edited.physics.lift_speed, 1.0..=100.0, "Lift speed (/s)").logarithmic(true));
+            ui.add(setting_slider(&mut edited.physics.drop_speed, 1.0..=100.0, "Drop speed (/s)").logarithmic(true));
+            ui.checkbox(&mut edited.physics.fall_off_desk, "Thrown objects can fall off the desk");
+
+            ui.add_space(10.0);
+            ui.label(RichText::new("PIXELATION").size(11.0).color(Color32::from_gray(150)));
+            ui.checkbox(&mut edited.pixelation.enabled, "Enabled");
+            ui.add_enabled_ui(edited.pixelation.enabled, |ui| {
+                ui.add(setting_slider(&mut edited.pixelation.pixel_size, 1..=32, "Pixel size"));
+                ui.add(setting_slider(&mut edited.pixelation.normal_edge_strength, 0.0..=1.0, "Normal edges"));
+                ui.add(setting_slider(&mut edited.pixelation.depth_edge_strength, 0.0..=1.0, "Depth edges"));
+            });
+
+            ui.add_space(10.0);
+            ui.label(RichText::new("CONTROLS").size(11.0).color(Color32::from_gray(150)));
+            if ui.button("⌨ Key bindings…").clicked() {
+                ui_state.bindings_open = true;
+                ui_state.binding_drafts = draft_bindings(settings);
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            if ui.button("Reset to defaults").clicked() {
+                edited = Config::default();
+            }
+            if let Some(status) = &ui_state.config_status {
+                ui.label(RichText::new(status).size(11.0).color(Color32::from_gray(180)));
+            }
+        });
+
+    if edited != *settings {
+        actions.push(UiAction::UpdateSettings(Box::new(edited)));
     }
 
     actions
 }
 
+/// Binding text of every action, in `Action::ALL` order
+fn draft_bindings(settings: &Config) -> Vec<String> {
+    Action::ALL
+        .iter()
+        .map(|action| settings.bindings.get(*action).to_string())
+        .collect()
+}
+
+/// Render the bindings window
+///
+/// Edits are checked as they are typed and only applied once every binding
+/// parses and none conflict.
+pub fn render_bindings_window(ctx: &egui::Context, ui_state: &mut UiState, settings: &Config) -> Vec<UiAction> {
+    let mut actions = Vec::new();
+
+    if !ui_state.bindings_open {
+        return actions;
+    }
+    if ui_state.binding_drafts.len() != Action::ALL.len() {
+        ui_state.binding_drafts = draft_bindings(settings);
+    }
+
+    let mut open = true;
+    egui::Window::new("⌨ Key bindings")
+        .open(&mut open)
+        .resizable(false)
+        .default_pos(egui::pos2(340.0, 120.0))
+        .show(ctx, |ui| {
+            ui.label(
+                RichText::new("e.g. A, Ctrl+Shift+Z, Shift+Scroll, RightMouse. Separate alternatives with commas.")
+                    .size(11.0)
+                    .color(Color32::from_gray(150)),
+            );
+            ui.add_space(5.0);
+
+            let mut edited = settings.bindings.clone();
+            let mut valid = true;
+            egui::Grid::new("bindings_grid").num_columns(2).show(ui, |ui| {
+                for (action, draft) in Action::ALL.iter().zip(ui_state.binding_drafts.iter_mut()) {
+                    ui.label(action.description());
+                    ui.vertical(|ui| {
+                        ui.add(egui::TextEdit::singleline(draft).desired_width(160.0));
+                        match parse_bindings(*action, draft) {
+                            Ok(_) => *edited.get_mut(*action) = draft.trim().to_string(),
+                            Err(e) => {
+                                valid = false;
+                                *edited.get_mut(*action) = String::new();
+                                ui.label(RichText::new(e).size(11.0).color(Color32::from_rgb(239, 68, 68)));
+                            }
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+            let keymap = KeyMap::from_config(&edited);
+            for conflict in keymap.conflicts() {
+                ui.label(RichText::new(conflict).size(11.0).color(Color32::from_rgb(251, 191, 36)));
+            }
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                let can_apply = valid && keymap.conflicts().is_empty() && edited != settings.bindings;
+                if ui.add_enabled(can_apply, egui::Button::new("Apply")).clicked() {
+                    let mut updated = settings.clone();
+                    updated.bindings = edited;
+                    actions.push(UiAction::UpdateSettings(Box::new(updated)));
+                }
+                if ui.button("Revert").clicked() {
+                    ui_state.binding_drafts = draft_bindings(settings);
+                }
+                if ui.button("Defaults").clicked() {
+                    ui_state.binding_drafts = draft_bindings(&Config::default());
+                }
+            });
+        });
+    ui_state.bindings_open = open;
+
+    actions
+}
+
+/// A labelled slider for a config value
+///
+/// The value is only clamped to the slider's range when the user moves it,
+/// so a setting from `config.toml` outside that range is shown as is rather
+/// than rewritten just by opening the window.
+fn setting_slider<'a, Num: egui::emath::Numeric>(
+    value: &'a mut Num,
+    range: RangeInclusive<Num>,
+    text: &str,
+) -> egui::Slider<'a> {
+    egui::Slider::new(value, range)
+        .clamping(egui::SliderClamping::Edits)
+        .text(text)
+}
+
+/// A labelled color picker editing a hex RGB color
+fn color_setting(ui: &mut egui::Ui, label: &str, color: &mut u32) {
+    ui.horizontal(|ui| {
+        let mut rgb = [(*color >> 16) as u8, (*color >> 8) as u8, *color as u8];
+        if ui.color_edit_button_srgb(&mut rgb).changed() {
+            *color = (rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32;
+        }
+        ui.label(label);
+    });
+}
+
 /// Render the right sidebar (object customization)
 pub fn render_right_sidebar(ctx: &egui::Context, ui_state: &mut UiState, object_name: Option<&str>) -> Vec<UiAction> {
     let mut actions = Vec::new();
@@ -417,7 +995,8 @@ pub fn render_right_sidebar(ctx: &egui::Context, ui_state: &mut UiState, object_
             egui::Grid::new("main_colors")
                 .spacing(Vec2::new(8.0, 8.0))
                 .show(ui, |ui| {
-                    for (i, (color, _name)) in COLOR_PRESETS.iter().enumerate() {
+                    for (i, preset) in ui_state.main_palette.iter().enumerate() {
+                        let color = &preset.color;
                         let r = ((color >> 16) & 0xFF) as u8;
                         let g = ((color >> 8) & 0xFF) as u8;
                         let b = (color & 0xFF) as u8;
@@ -454,7 +1033,8 @@ pub fn render_right_sidebar(ctx: &egui::Context, ui_state: &mut UiState, object_
             egui::Grid::new("accent_colors")
                 .spacing(Vec2::new(8.0, 8.0))
                 .show(ui, |ui| {
-                    for (i, (color, _name)) in ACCENT_COLOR_PRESETS.iter().enumerate() {
+                    for (i, preset) in ui_state.accent_palette.iter().enumerate() {
+                        let color = &preset.color;
                         let r = ((color >> 16) & 0xFF) as u8;
                         let g = ((color >> 8) & 0xFF) as u8;
                         let b = (color & 0xFF) as u8;